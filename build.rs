@@ -1,4 +1,7 @@
 fn main() {
-    embed_resource::compile("resources/resources.rc", embed_resource::NONE);
+    // The icons are Windows resources; other platforms load the PNGs directly.
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+        embed_resource::compile("resources/resources.rc", embed_resource::NONE);
+    }
     slint_build::compile("resources/ui/app.slint").unwrap();
 }
@@ -1,53 +1,77 @@
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
-mod dialog;
+use log::{error, info};
+use slint::ComponentHandle;
+
+pub mod dialog;
+pub mod theme;
 mod window;
 
-use crate::{controllers::monitor::Monitor, hotkey::HotkeyWorker};
+use crate::controllers::{monitor::Monitor, saver::Saver, scheduler::Scheduler};
+use crate::hotkey::HotkeyWorker;
+use crate::{autostart, config};
 use crate::{manager::Manager, tray::Tray, ui::MainWindow};
 
 pub struct Application {
-    config: PathBuf,
     pub window: MainWindow,
     tray: Tray,
     manager: Arc<RwLock<Manager>>,
+    scheduler: Arc<Scheduler>,
     hotkey: HotkeyWorker,
+    saver: Saver,
 }
 
 impl Application {
-    pub fn new() -> Self {
-        let s = dirs::config_dir()
-            .expect("config_dir should be valid")
-            .to_str()
-            .expect("config_dir should contains only UTF-8")
-            .to_owned();
-        let mut config = PathBuf::from(&s);
-        config.push("monitorhosts.json");
-        let mgr = Manager::with_config(&config).unwrap_or_else(Manager::new);
+    pub fn new(config: PathBuf, profile: Option<String>) -> Self {
+        info!("using config '{config:?}'");
+        let mgr = config::load(&config)
+            .map(Manager::with_document)
+            .unwrap_or_else(Manager::new);
         let manager = Arc::new(RwLock::new(mgr));
-        let window = window::setup(manager.clone());
-        let tray = Tray::new(&window);
-        let hotkey = HotkeyWorker::new(manager.clone());
+        let scheduler = Arc::new(Scheduler::new());
+        let window = window::setup(manager.clone(), scheduler.clone(), config.clone());
+        window.set_profile(profile.clone().unwrap_or_default().into());
+        let startup = manager.read().unwrap().settings.startup.clone();
+        if startup.show_window {
+            window.show().unwrap();
+        }
+        // Registered again so that the entry follows the program if it moved.
+        if startup.autostart {
+            if let Err(err) = autostart::set(true, &config) {
+                error!("failed to register autostart: {err}");
+            }
+        }
+        let title = match &profile {
+            Some(profile) => format!("MonitorHosts - {profile}"),
+            None => "MonitorHosts".to_owned(),
+        };
+        let tray = Tray::new(
+            &window,
+            title,
+            config.clone(),
+            manager.clone(),
+            scheduler.clone(),
+        );
+        let hotkey = HotkeyWorker::new(manager.clone(), scheduler.clone(), &window);
+        let saver = Saver::new(manager.clone(), config);
 
         Application {
-            config,
             manager,
+            scheduler,
             window,
             tray,
             hotkey,
+            saver,
         }
     }
 
     pub fn run(self) {
-        let monitor = Monitor::new(self.manager.clone(), &self.window);
+        let monitor = Monitor::new(self.manager.clone(), self.scheduler.clone(), &self.window);
         slint::run_event_loop_until_quit().unwrap();
         self.tray.join();
         monitor.join();
         self.hotkey.join();
-
-        let hosts = self.manager.read().unwrap().hosts.clone();
-        let toml = serde_json::to_string(&hosts).unwrap();
-        std::fs::write(&self.config, &toml).expect("config '{config:?}' should be writable");
+        self.saver.join();
     }
 }
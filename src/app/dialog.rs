@@ -1,39 +1,90 @@
 use std::{
-    collections::BTreeMap,
-    sync::{Arc, RwLock},
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
 };
 
+use chrono::{Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Weekday};
 use log::{trace, warn};
 use slint::*;
 
-use crate::ui::HostConfigModel;
+use super::theme::themed;
+use crate::autostart;
+use crate::controllers::discovery::{self, Found};
+use crate::controllers::scheduler::{ProbeRequest, Scheduler};
+use crate::import::{self, Candidate, Duplicate};
+use crate::maintenance::{Maintenance, MaintenanceMode, Schedule, Target};
+use crate::settings::{Settings, Theme};
+use crate::ui::{HostConfigModel, ImportItem, MaintenanceModel, SettingsModel};
 use crate::{
     manager::{HostConfig, Manager, Port},
-    ui::{ConfirmDialog, HostConfigDialog},
+    ui::{
+        ConfirmDialog, DiscoveryDialog, HostConfigDialog, ImportDialog, MaintenanceDialog,
+        MessageDialog, SettingsDialog,
+    },
 };
 
 pub fn add_dialog(mgr: Arc<RwLock<Manager>>) -> HostConfigDialog {
-    let dialog = HostConfigDialog::new().unwrap();
+    adding_dialog(mgr, None, |_| {})
+}
+
+/// Asks before adding `host`, found by the hotkey, with what was detected
+/// filled in, and probes it once added.
+pub fn confirm_add_dialog(
+    mgr: Arc<RwLock<Manager>>,
+    scheduler: Arc<Scheduler>,
+    host: HostConfig,
+) -> HostConfigDialog {
+    let model = host_model(&host);
+    let dialog = adding_dialog(mgr, Some(host), move |name| {
+        scheduler.probe_now(ProbeRequest::Host(name.to_owned()));
+    });
+    dialog.set_dialog_title("从剪贴板添加".into());
+    dialog.set_input_model(model);
+    dialog
+}
+
+/// A dialog adding a new host, starting from `base` while its name is kept.
+fn adding_dialog(
+    mgr: Arc<RwLock<Manager>>,
+    base: Option<HostConfig>,
+    added: impl Fn(&str) + 'static,
+) -> HostConfigDialog {
+    let dialog = themed(HostConfigDialog::new().unwrap());
+    on_detect_services(&dialog, mgr.clone());
     let dialog_weak = dialog.as_weak();
     let dialog_clone = dialog_weak.clone();
     let mgr = mgr.clone();
     dialog.on_action_ok(move |host| {
         trace!("add-dialog::on_action_ok: {host:?}");
+        let dialog = dialog_clone.unwrap();
         let name = host.name.to_string();
         if !mgr.read().unwrap().contains_host(&name) {
-            let mut ports = BTreeMap::new();
-            ports.insert(Port::Http, host.http);
-            ports.insert(Port::Https, host.https);
-            ports.insert(Port::Ssh, host.ssh);
-            ports.insert(Port::Vnc, host.vnc);
-            ports.insert(Port::Ipmi, host.ipmi);
+            let mut config = match base.clone().filter(|base| base.name == name) {
+                Some(mut base) => {
+                    base.ports = ports(&host);
+                    base
+                }
+                None => HostConfig::new(name.clone(), ports(&host)),
+            };
+            if let Err(err) = apply(&mut config, &host, &mgr.read().unwrap().hosts) {
+                dialog.set_detect_status(err.into());
+                return;
+            }
             trace!("calling hmanager::add_host...");
-            mgr.write().unwrap().add_host(HostConfig::new(name, ports));
+            mgr.write().unwrap().add_host(config);
             trace!("calling hmanager::add_host done");
+            added(&name);
         } else {
             warn!("host with name {name} already exists");
         }
-        dialog_clone.unwrap().hide().unwrap();
+        dialog.hide().unwrap();
     });
     let dialog_clone = dialog_weak.clone();
     dialog.on_action_cancel(move || {
@@ -48,38 +99,49 @@ pub fn config_dialog(mgr: Arc<RwLock<Manager>>, index: usize) -> HostConfigDialo
     let host = m
         .get_host(index)
         .expect("the index {index} should be valid");
-    let dialog = HostConfigDialog::new().unwrap();
+    // The config may be reloaded while the dialog is open, so the host is
+    // found again by name rather than by row.
+    let name = host.name.clone();
+    let dialog = themed(HostConfigDialog::new().unwrap());
     dialog.set_dialog_title("配置".into());
-    let mut model = HostConfigModel {
-        name: SharedString::from(&host.name),
-        ..Default::default()
-    };
-    host.ports.iter().for_each(|(port, enabled)| match port {
-        Port::Http => model.http = *enabled,
-        Port::Https => model.https = *enabled,
-        Port::Ssh => model.ssh = *enabled,
-        Port::Vnc => model.vnc = *enabled,
-        Port::Ipmi => model.ipmi = *enabled,
-    });
-    dialog.set_input_model(model);
+    dialog.set_input_model(host_model(host));
+    on_detect_services(&dialog, mgr.clone());
     let dialog_weak = dialog.as_weak();
     let dialog_clone = dialog_weak.clone();
     let mgr = mgr.clone();
     dialog.on_action_ok(move |host| {
         trace!("config-dialog::on_action_ok: {host:?}");
-        let name = host.name.to_string();
-        let mut ports = BTreeMap::new();
-        ports.insert(Port::Http, host.http);
-        ports.insert(Port::Https, host.https);
-        ports.insert(Port::Ssh, host.ssh);
-        ports.insert(Port::Vnc, host.vnc);
-        ports.insert(Port::Ipmi, host.ipmi);
+        let dialog = dialog_clone.unwrap();
+        let m = mgr.read().unwrap();
+        let Some(mut config) = m.hosts.iter().find(|host| host.name == name).cloned() else {
+            warn!("host {name} is gone");
+            dialog.set_detect_status(gone(&name).into());
+            return;
+        };
+        config.name = host.name.to_string();
+        config.ports = ports(&host);
+        let others: Vec<HostConfig> = m
+            .hosts
+            .iter()
+            .filter(|host| host.name != name)
+            .cloned()
+            .collect();
+        drop(m);
+        if let Err(err) = apply(&mut config, &host, &others) {
+            dialog.set_detect_status(err.into());
+            return;
+        }
         trace!("calling hmanager::update_host...");
-        mgr.write()
-            .unwrap()
-            .update_host(index, HostConfig::new(name, ports));
+        let mut m = mgr.write().unwrap();
+        let Some(index) = m.host_index(&name) else {
+            warn!("host {name} is gone");
+            dialog.set_detect_status(gone(&name).into());
+            return;
+        };
+        m.update_host(index, config);
+        drop(m);
         trace!("calling hmanager::update_host done");
-        dialog_clone.unwrap().hide().unwrap();
+        dialog.hide().unwrap();
     });
     let dialog_clone = dialog_weak.clone();
     dialog.on_action_cancel(move || {
@@ -89,12 +151,116 @@ pub fn config_dialog(mgr: Arc<RwLock<Manager>>, index: usize) -> HostConfigDialo
     dialog
 }
 
+fn gone(name: &str) -> String {
+    std::format!("主机 '{name}' 已被删除或改名，可能是配置文件已重新加载")
+}
+
+/// Scans the host typed in the dialog and checks what answers.
+fn on_detect_services(dialog: &HostConfigDialog, mgr: Arc<RwLock<Manager>>) {
+    let dialog_weak = dialog.as_weak();
+    dialog.on_detect_services(move |name| {
+        trace!("host-dialog::on_detect_services: {name}");
+        let dialog = dialog_weak.unwrap();
+        dialog.set_detecting(true);
+        dialog.set_detect_status("".into());
+        let name = name.trim().to_owned();
+        let settings = mgr.read().unwrap().settings.detection.clone();
+        let weak = dialog.as_weak();
+        thread::spawn(move || {
+            let result = discovery::detect(&name, &settings);
+            let shown = weak.upgrade_in_event_loop(move |dialog| {
+                dialog.set_detecting(false);
+                let status = match result {
+                    Ok(ports) if ports.is_empty() => "未发现开放的端口".to_owned(),
+                    Ok(ports) => {
+                        let found = HostConfig::with_ports(name, &ports);
+                        dialog.invoke_apply_detected(host_model(&found));
+                        let ports: Vec<String> = ports.iter().map(u16::to_string).collect();
+                        std::format!("发现开放端口：{}", ports.join(", "))
+                    }
+                    Err(err) => std::format!("检测失败：{err}"),
+                };
+                dialog.set_detect_status(status.into());
+            });
+            if let Err(err) = shown {
+                warn!("failed to show detected services: {err}");
+            }
+        });
+    });
+}
+
+fn host_model(host: &HostConfig) -> HostConfigModel {
+    let mut model = HostConfigModel {
+        name: SharedString::from(&host.name),
+        label: SharedString::from(host.label.as_deref().unwrap_or_default()),
+        parent: SharedString::from(host.parent.as_deref().unwrap_or_default()),
+        group: SharedString::from(host.group.as_deref().unwrap_or_default()),
+        others: host
+            .tcp_ports()
+            .iter()
+            .map(u16::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+            .into(),
+        ..Default::default()
+    };
+    host.ports.iter().for_each(|(port, enabled)| match port {
+        Port::Http => model.http = *enabled,
+        Port::Https => model.https = *enabled,
+        Port::Ssh => model.ssh = *enabled,
+        Port::Vnc => model.vnc = *enabled,
+        Port::Ipmi => model.ipmi = *enabled,
+    });
+    model
+}
+
+fn ports(host: &HostConfigModel) -> BTreeMap<Port, bool> {
+    let mut ports = BTreeMap::new();
+    ports.insert(Port::Http, host.http);
+    ports.insert(Port::Https, host.https);
+    ports.insert(Port::Ssh, host.ssh);
+    ports.insert(Port::Vnc, host.vnc);
+    ports.insert(Port::Ipmi, host.ipmi);
+    ports
+}
+
+/// Copies the free text fields of the dialog into `config`, whose parent has
+/// to be one of the other `hosts`.
+fn apply(
+    config: &mut HostConfig,
+    host: &HostConfigModel,
+    hosts: &[HostConfig],
+) -> Result<(), String> {
+    let others = import::parse_port_numbers(&host.others)
+        .map_err(|err| std::format!("无效的端口：{err}"))?;
+    // Listing the port of an unchecked fixed check would turn it back on.
+    let unchecked = others
+        .iter()
+        .filter_map(|number| Port::from_u16(*number))
+        .find(|port| !config.ports.get(port).copied().unwrap_or_default());
+    if let Some(port) = unchecked {
+        return Err(std::format!("端口 {} 请勾选“{port}”检测", port.u16()));
+    }
+    let parent = optional(&host.parent);
+    if let Some(parent) = &parent {
+        if *parent == config.name || !hosts.iter().any(|host| host.name == *parent) {
+            return Err(std::format!("未知的上级主机：{parent}"));
+        }
+    }
+    config.set_tcp_ports(&others);
+    config.label = optional(&host.label);
+    config.parent = parent;
+    config.group = optional(&host.group);
+    Ok(())
+}
+
 pub fn remove_dialog(mgr: Arc<RwLock<Manager>>, index: usize) -> ConfirmDialog {
     let m = mgr.read().unwrap();
     let host = m
         .get_host(index)
         .expect("the index {index} should be valid");
-    let dialog = ConfirmDialog::new().unwrap();
+    let name = host.name.clone();
+    let dialog = themed(ConfirmDialog::new().unwrap());
     dialog.set_dialog_title("删除".into());
     dialog.set_confirm_message(slint::format!("确定要删除 '{}'?", host.name));
     let dialog_weak = dialog.as_weak();
@@ -102,7 +268,14 @@ pub fn remove_dialog(mgr: Arc<RwLock<Manager>>, index: usize) -> ConfirmDialog {
     let mgr = mgr.clone();
     dialog.on_action_ok(move || {
         trace!("remove-dialog::on_action_ok");
-        mgr.write().unwrap().remove_host(index);
+        let mut m = mgr.write().unwrap();
+        match m.host_index(&name) {
+            Some(index) => m.remove_host(index),
+            None => {
+                warn!("host {name} is gone");
+                message_dialog("删除", &gone(&name)).show().unwrap();
+            }
+        }
         dialog_clone.unwrap().hide().unwrap();
     });
     let dialog_clone = dialog_weak.clone();
@@ -113,8 +286,61 @@ pub fn remove_dialog(mgr: Arc<RwLock<Manager>>, index: usize) -> ConfirmDialog {
     dialog
 }
 
+pub fn maintenance_dialog(mgr: Arc<RwLock<Manager>>, index: usize) -> MaintenanceDialog {
+    let m = mgr.read().unwrap();
+    let host = m
+        .get_host(index)
+        .expect("the index {index} should be valid");
+    let name = host.name.clone();
+    let group = host.group.clone();
+    let dialog = themed(MaintenanceDialog::new().unwrap());
+    dialog.set_host_name(SharedString::from(&host.name));
+    dialog.set_group_name(SharedString::from(group.as_deref().unwrap_or_default()));
+    let target = move |whole_group: bool| match (&group, whole_group) {
+        (Some(group), true) => Target::Group(group.clone()),
+        _ => Target::Host(name.clone()),
+    };
+    let dialog_weak = dialog.as_weak();
+    let dialog_clone = dialog_weak.clone();
+    let manager = mgr.clone();
+    let target_clone = target.clone();
+    dialog.on_action_ok(move |model| {
+        trace!("maintenance-dialog::on_action_ok: {model:?}");
+        let dialog = dialog_clone.unwrap();
+        match maintenance(&model) {
+            Ok(maintenance) => {
+                manager
+                    .write()
+                    .unwrap()
+                    .add_maintenance(&target_clone(model.group), maintenance);
+                dialog.hide().unwrap();
+            }
+            Err(err) => {
+                warn!("invalid maintenance {model:?}: {err}");
+                dialog.set_error_message(err.into());
+            }
+        }
+    });
+    let dialog_clone = dialog_weak.clone();
+    let manager = mgr.clone();
+    dialog.on_action_clear(move |whole_group| {
+        trace!("maintenance-dialog::on_action_clear");
+        manager
+            .write()
+            .unwrap()
+            .clear_maintenance(&target(whole_group));
+        dialog_clone.unwrap().hide().unwrap();
+    });
+    let dialog_clone = dialog_weak.clone();
+    dialog.on_action_cancel(move || {
+        trace!("maintenance-dialog::on_action_cancel");
+        dialog_clone.unwrap().hide().unwrap();
+    });
+    dialog
+}
+
 pub fn clear_dialog(mgr: Arc<RwLock<Manager>>) -> ConfirmDialog {
-    let dialog = ConfirmDialog::new().unwrap();
+    let dialog = themed(ConfirmDialog::new().unwrap());
     dialog.set_dialog_title("清空".into());
     dialog.set_confirm_message("确定要清空所有主机么？".into());
     let dialog_weak = dialog.as_weak();
@@ -132,3 +358,342 @@ pub fn clear_dialog(mgr: Arc<RwLock<Manager>>) -> ConfirmDialog {
     });
     dialog
 }
+
+/// Lets the user pick which of `hosts`, read from `source`, to add.
+pub fn import_dialog(
+    mgr: Arc<RwLock<Manager>>,
+    source: &str,
+    hosts: Result<Vec<HostConfig>, String>,
+) -> ImportDialog {
+    let dialog = themed(ImportDialog::new().unwrap());
+    dialog.set_source(source.into());
+    let candidates = match hosts {
+        Ok(hosts) => import::preview(hosts, &mgr.read().unwrap().hosts),
+        Err(err) => {
+            warn!("failed to import '{source}': {err}");
+            dialog.set_error_message(std::format!("无法读取 '{source}'：{err}").into());
+            Vec::new()
+        }
+    };
+    let items: Rc<VecModel<ImportItem>> = Rc::new(VecModel::from(
+        candidates.iter().map(import_item).collect::<Vec<_>>(),
+    ));
+    dialog.set_items(items.clone().into());
+    let dialog_weak = dialog.as_weak();
+    let dialog_clone = dialog_weak.clone();
+    let mgr = mgr.clone();
+    dialog.on_action_ok(move || {
+        trace!("import-dialog::on_action_ok");
+        let mut m = mgr.write().unwrap();
+        for (candidate, item) in candidates.iter().zip(items.iter()) {
+            if item.checked && !m.contains_host(&candidate.host.name) {
+                m.add_host(candidate.host.clone());
+            }
+        }
+        dialog_clone.unwrap().hide().unwrap();
+    });
+    let dialog_clone = dialog_weak.clone();
+    dialog.on_action_cancel(move || {
+        trace!("import-dialog::on_action_cancel");
+        dialog_clone.unwrap().hide().unwrap();
+    });
+    dialog
+}
+
+pub fn discovery_dialog(mgr: Arc<RwLock<Manager>>) -> DiscoveryDialog {
+    let dialog = themed(DiscoveryDialog::new().unwrap());
+    dialog.set_items(Rc::new(VecModel::<ImportItem>::default()).into());
+    // Hosts found by the current sweep, by address.
+    let found: Arc<Mutex<HashMap<String, HostConfig>>> = Arc::default();
+    let cancel = Rc::new(RefCell::new(Arc::new(AtomicBool::new(false))));
+    let dialog_weak = dialog.as_weak();
+
+    let dialog_clone = dialog_weak.clone();
+    let manager = mgr.clone();
+    let hosts = found.clone();
+    let flag = cancel.clone();
+    dialog.on_start_scan(move |range| {
+        trace!("discovery-dialog::on_start_scan: {range}");
+        let dialog = dialog_clone.unwrap();
+        let targets = match discovery::parse_range(&range) {
+            Ok(targets) => targets,
+            Err(err) => {
+                dialog.set_error_message(err.into());
+                return;
+            }
+        };
+        let total = targets.len();
+        let terminate = Arc::new(AtomicBool::new(false));
+        flag.replace(terminate.clone())
+            .store(true, Ordering::Relaxed);
+        hosts.lock().unwrap().clear();
+        dialog.set_items(Rc::new(VecModel::<ImportItem>::default()).into());
+        dialog.set_error_message("".into());
+        dialog.set_progress(std::format!("正在扫描 0/{total}").into());
+        dialog.set_scanning(true);
+
+        // Reports come from several tasks, and `Weak` is not `Sync`.
+        let weak = Mutex::new(dialog.as_weak());
+        let mgr = manager.clone();
+        let hosts = hosts.clone();
+        let stale = terminate.clone();
+        let report = Arc::new(move |done: usize, result: Option<Found>| {
+            if stale.load(Ordering::Relaxed) {
+                return;
+            }
+            let item = result.map(|found| {
+                let host = found.host();
+                let existing = mgr.read().unwrap().contains_host(&host.name);
+                let mut item = import_item(&Candidate {
+                    host: host.clone(),
+                    duplicate: existing.then_some(Duplicate::Existing),
+                });
+                if found.ping {
+                    item.detail = std::format!("Ping {}", item.detail).into();
+                }
+                hosts.lock().unwrap().insert(host.name.clone(), host);
+                item
+            });
+            let progress = weak.lock().unwrap().upgrade_in_event_loop(move |dialog| {
+                let items = dialog.get_items();
+                let items = items
+                    .as_any()
+                    .downcast_ref::<VecModel<ImportItem>>()
+                    .unwrap();
+                if let Some(item) = item {
+                    items.push(item);
+                }
+                let count = items.row_count();
+                dialog
+                    .set_progress(std::format!("正在扫描 {done}/{total}，发现 {count} 台").into());
+            });
+            if let Err(err) = progress {
+                warn!("failed to report sweep progress: {err}");
+            }
+        });
+        let settings = manager.read().unwrap().settings.discovery.clone();
+        let weak = dialog.as_weak();
+        thread::spawn(move || {
+            discovery::sweep(targets, &settings, terminate.clone(), report);
+            if terminate.load(Ordering::Relaxed) {
+                return;
+            }
+            let finished = weak.upgrade_in_event_loop(move |dialog| {
+                let count = dialog.get_items().row_count();
+                dialog.set_progress(
+                    std::format!("扫描完成，共 {total} 个地址，发现 {count} 台").into(),
+                );
+                dialog.set_scanning(false);
+            });
+            if let Err(err) = finished {
+                warn!("failed to report sweep result: {err}");
+            }
+        });
+    });
+
+    let dialog_clone = dialog_weak.clone();
+    let flag = cancel.clone();
+    dialog.on_stop_scan(move || {
+        trace!("discovery-dialog::on_stop_scan");
+        flag.borrow().store(true, Ordering::Relaxed);
+        let dialog = dialog_clone.unwrap();
+        dialog.set_progress(
+            std::format!("已停止，发现 {} 台", dialog.get_items().row_count()).into(),
+        );
+        dialog.set_scanning(false);
+    });
+
+    let dialog_clone = dialog_weak.clone();
+    let flag = cancel.clone();
+    dialog.on_action_ok(move || {
+        trace!("discovery-dialog::on_action_ok");
+        flag.borrow().store(true, Ordering::Relaxed);
+        let dialog = dialog_clone.unwrap();
+        let hosts = found.lock().unwrap();
+        let mut m = mgr.write().unwrap();
+        for item in dialog.get_items().iter() {
+            match hosts.get(item.name.as_str()) {
+                Some(host) if item.checked && !m.contains_host(&host.name) => {
+                    m.add_host(host.clone());
+                }
+                _ => {}
+            }
+        }
+        dialog.hide().unwrap();
+    });
+
+    let dialog_clone = dialog_weak.clone();
+    dialog.on_action_cancel(move || {
+        trace!("discovery-dialog::on_action_cancel");
+        cancel.borrow().store(true, Ordering::Relaxed);
+        dialog_clone.unwrap().hide().unwrap();
+    });
+    dialog
+}
+
+/// Edits the settings, which apply as soon as the dialog is accepted.
+pub fn settings_dialog(mgr: Arc<RwLock<Manager>>, config: PathBuf) -> SettingsDialog {
+    let dialog = themed(SettingsDialog::new().unwrap());
+    dialog.set_input_model(settings_model(&mgr.read().unwrap().settings));
+    let dialog_weak = dialog.as_weak();
+    let dialog_clone = dialog_weak.clone();
+    dialog.on_action_ok(move |model| {
+        trace!("settings-dialog::on_action_ok: {model:?}");
+        let dialog = dialog_clone.unwrap();
+        let current = mgr.read().unwrap().settings.clone();
+        let settings = apply_settings(current.clone(), &model);
+        if settings.startup.autostart != current.startup.autostart {
+            if let Err(err) = autostart::set(settings.startup.autostart, &config) {
+                warn!("failed to change autostart: {err}");
+                dialog.set_error_message(std::format!("无法设置自动启动：{err}").into());
+                return;
+            }
+        }
+        mgr.write().unwrap().set_settings(settings);
+        dialog.hide().unwrap();
+    });
+    dialog.on_action_cancel(move || {
+        trace!("settings-dialog::on_action_cancel");
+        dialog_weak.unwrap().hide().unwrap();
+    });
+    dialog
+}
+
+fn settings_model(settings: &Settings) -> SettingsModel {
+    let hotkey = |key: &Option<String>| SharedString::from(key.as_deref().unwrap_or_default());
+    let hotkeys = &settings.hotkeys;
+    SettingsModel {
+        probe_interval: settings.probe.interval_secs as i32,
+        probe_timeout: settings.probe.timeout_ms as i32,
+        probe_concurrency: settings.probe.concurrency as i32,
+        ping_count: settings.liveness.count as i32,
+        ping_timeout: settings.liveness.timeout_ms as i32,
+        discovery_concurrency: settings.discovery.concurrency as i32,
+        discovery_rate: settings.discovery.rate as i32,
+        notify: settings.notifications.enabled,
+        notify_recovery: settings.notifications.recovery,
+        notify_degraded: settings.notifications.degraded,
+        notify_flapping: settings.notifications.flapping,
+        hotkey_add_host: hotkey(&hotkeys.add_host),
+        hotkey_probe_all: hotkey(&hotkeys.probe_all),
+        hotkey_toggle_window: hotkey(&hotkeys.toggle_window),
+        hotkey_toggle_pause: hotkey(&hotkeys.toggle_pause),
+        confirm_add: hotkeys.confirm_add,
+        theme: Theme::ALL
+            .iter()
+            .position(|theme| *theme == settings.ui.theme)
+            .unwrap_or_default() as i32,
+        show_window: settings.startup.show_window,
+        autostart: settings.startup.autostart,
+    }
+}
+
+/// Copies the fields shown in the dialog into `settings`, leaving the rest.
+fn apply_settings(mut settings: Settings, model: &SettingsModel) -> Settings {
+    let number = |value: i32| value.max(0) as u64;
+    settings.probe.interval_secs = number(model.probe_interval);
+    settings.probe.timeout_ms = number(model.probe_timeout);
+    settings.probe.concurrency = number(model.probe_concurrency) as usize;
+    settings.liveness.count = number(model.ping_count) as u16;
+    settings.liveness.timeout_ms = number(model.ping_timeout);
+    settings.discovery.concurrency = number(model.discovery_concurrency) as usize;
+    settings.discovery.rate = number(model.discovery_rate) as u32;
+    settings.notifications.enabled = model.notify;
+    settings.notifications.recovery = model.notify_recovery;
+    settings.notifications.degraded = model.notify_degraded;
+    settings.notifications.flapping = model.notify_flapping;
+    settings.hotkeys.add_host = optional(&model.hotkey_add_host);
+    settings.hotkeys.probe_all = optional(&model.hotkey_probe_all);
+    settings.hotkeys.toggle_window = optional(&model.hotkey_toggle_window);
+    settings.hotkeys.toggle_pause = optional(&model.hotkey_toggle_pause);
+    settings.hotkeys.confirm_add = model.confirm_add;
+    settings.ui.theme = Theme::ALL
+        .get(model.theme as usize)
+        .copied()
+        .unwrap_or_default();
+    settings.startup.show_window = model.show_window;
+    settings.startup.autostart = model.autostart;
+    settings
+}
+
+pub fn message_dialog(title: &str, message: &str) -> MessageDialog {
+    let dialog = themed(MessageDialog::new().unwrap());
+    dialog.set_dialog_title(title.into());
+    dialog.set_message(message.into());
+    let dialog_weak = dialog.as_weak();
+    dialog.on_action_ok(move || {
+        trace!("message-dialog::on_action_ok");
+        dialog_weak.unwrap().hide().unwrap();
+    });
+    dialog
+}
+
+fn import_item(candidate: &Candidate) -> ImportItem {
+    ImportItem {
+        checked: candidate.duplicate.is_none(),
+        name: SharedString::from(&candidate.host.name),
+        label: SharedString::from(candidate.host.label.as_deref().unwrap_or_default()),
+        detail: candidate.describe().into(),
+        note: match candidate.duplicate {
+            None => "".into(),
+            Some(Duplicate::Existing) => "已存在".into(),
+            Some(Duplicate::Repeated) => "重复".into(),
+        },
+    }
+}
+
+fn optional(text: &SharedString) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_owned())
+    }
+}
+
+fn maintenance(model: &MaintenanceModel) -> Result<Maintenance, String> {
+    let mode = if model.silence {
+        MaintenanceMode::Silence
+    } else {
+        MaintenanceMode::Pause
+    };
+    let schedule = if model.weekly {
+        let days: Vec<Weekday> = [
+            (model.mon, Weekday::Mon),
+            (model.tue, Weekday::Tue),
+            (model.wed, Weekday::Wed),
+            (model.thu, Weekday::Thu),
+            (model.fri, Weekday::Fri),
+            (model.sat, Weekday::Sat),
+            (model.sun, Weekday::Sun),
+        ]
+        .into_iter()
+        .filter_map(|(checked, day)| checked.then_some(day))
+        .collect();
+        if days.is_empty() {
+            return Err("请至少选择一天".to_owned());
+        }
+        let time = |text: &SharedString| {
+            NaiveTime::parse_from_str(text.trim(), "%H:%M")
+                .map_err(|_| std::format!("无效的时间 '{text}'，格式应为 HH:MM"))
+        };
+        Schedule::Weekly {
+            days,
+            start: time(&model.start)?,
+            end: time(&model.end)?,
+        }
+    } else if !model.until.trim().is_empty() {
+        let until = NaiveDateTime::parse_from_str(model.until.trim(), "%Y-%m-%d %H:%M")
+            .ok()
+            .and_then(|until| Local.from_local_datetime(&until).earliest())
+            .ok_or_else(|| {
+                std::format!("无效的时间 '{}'，格式应为 YYYY-MM-DD HH:MM", model.until)
+            })?;
+        Schedule::Until { until }
+    } else {
+        Schedule::Until {
+            until: Local::now() + Duration::minutes(i64::from(model.minutes)),
+        }
+    };
+    Ok(Maintenance::new(mode, schedule))
+}
@@ -0,0 +1,35 @@
+use std::cell::Cell;
+
+use slint::{ComponentHandle, Global};
+
+use crate::settings::Theme;
+use crate::ui::ThemeSelector;
+
+thread_local! {
+    /// The theme last applied, given to windows opened afterwards.
+    static CURRENT: Cell<Theme> = const { Cell::new(Theme::System) };
+}
+
+/// Switches `component` to `theme`, and windows opened from now on as well.
+pub fn apply<C>(component: &C, theme: Theme)
+where
+    C: ComponentHandle,
+    for<'a> ThemeSelector<'a>: Global<'a, C>,
+{
+    CURRENT.set(theme);
+    component
+        .global::<ThemeSelector>()
+        .invoke_apply(theme.as_str().into());
+}
+
+/// Gives a newly created window the current theme.
+pub fn themed<C>(component: C) -> C
+where
+    C: ComponentHandle,
+    for<'a> ThemeSelector<'a>: Global<'a, C>,
+{
+    component
+        .global::<ThemeSelector>()
+        .invoke_apply(CURRENT.get().as_str().into());
+    component
+}
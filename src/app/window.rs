@@ -1,11 +1,20 @@
+use std::future::Future;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 
+use chrono::Local;
+use log::{error, info};
+use rfd::FileHandle;
 use slint::*;
+use tokio::runtime::Runtime;
 
+use crate::controllers::scheduler::{ProbeRequest, Scheduler};
+use crate::{export, import};
 use crate::{manager::Manager, ui::MainWindow};
 
-pub fn setup(mgr: Arc<RwLock<Manager>>) -> MainWindow {
+pub fn setup(mgr: Arc<RwLock<Manager>>, scheduler: Arc<Scheduler>, config: PathBuf) -> MainWindow {
     let window = MainWindow::new().unwrap();
+    super::theme::apply(&window, mgr.read().unwrap().settings.ui.theme);
 
     let manager = mgr.clone();
     window.on_add_host(move || {
@@ -29,11 +38,128 @@ pub fn setup(mgr: Arc<RwLock<Manager>>) -> MainWindow {
         }
     });
 
+    let manager = mgr.clone();
+    window.on_maintain_host(move |index| {
+        if index >= 0 {
+            let dialog = super::dialog::maintenance_dialog(manager.clone(), index as usize);
+            dialog.show().unwrap();
+        }
+    });
+
     let manager = mgr.clone();
     window.on_clear_hosts(move || {
         let dialog = super::dialog::clear_dialog(manager.clone());
         dialog.show().unwrap();
     });
 
+    let manager = mgr.clone();
+    window.on_import_hosts(move || {
+        let picked = pick_file(
+            rfd::AsyncFileDialog::new()
+                .set_title("导入主机")
+                .add_filter("CSV", &["csv"])
+                .add_filter("文本", &["txt", "list", "hosts"])
+                .add_filter("nmap XML", &["xml"])
+                .add_filter("所有文件", &["*"])
+                .pick_file(),
+        );
+        if let Some(path) = picked {
+            let hosts = import::read(&path, None);
+            let dialog =
+                super::dialog::import_dialog(manager.clone(), &path.display().to_string(), hosts);
+            dialog.show().unwrap();
+        }
+    });
+
+    let manager = mgr.clone();
+    window.on_import_ssh_hosts(move || match import::ssh_config() {
+        Some(path) => {
+            let hosts = import::read(&path, Some(import::Format::Ssh));
+            let dialog =
+                super::dialog::import_dialog(manager.clone(), &path.display().to_string(), hosts);
+            dialog.show().unwrap();
+        }
+        None => super::dialog::message_dialog("导入SSH配置", "未找到 ~/.ssh/config")
+            .show()
+            .unwrap(),
+    });
+
+    let manager = mgr.clone();
+    window.on_discover_hosts(move || {
+        let dialog = super::dialog::discovery_dialog(manager.clone());
+        dialog.show().unwrap();
+    });
+
+    let manager = mgr.clone();
+    window.on_export_hosts(move || {
+        let picked = pick_file(
+            rfd::AsyncFileDialog::new()
+                .set_title("导出主机状态")
+                .set_file_name(
+                    Local::now()
+                        .format("monitorhosts-%Y%m%d-%H%M.md")
+                        .to_string(),
+                )
+                .add_filter("Markdown", &["md"])
+                .add_filter("CSV", &["csv"])
+                .add_filter("JSON", &["json"])
+                .save_file(),
+        );
+        if let Some(path) = picked {
+            let snapshot = manager.read().unwrap().snapshot();
+            match export::write(&path, &snapshot, None) {
+                Ok(()) => info!("exported {} hosts to '{path:?}'", snapshot.configs.len()),
+                Err(err) => {
+                    error!("failed to export to '{path:?}': {err}");
+                    let message = std::format!("无法写入 '{}'：{err}", path.display());
+                    super::dialog::message_dialog("导出失败", &message)
+                        .show()
+                        .unwrap();
+                }
+            }
+        }
+    });
+
+    let manager = mgr.clone();
+    let sched = scheduler.clone();
+    window.on_probe_host(move |index| {
+        if index >= 0 {
+            if let Some(host) = manager.read().unwrap().get_host(index as usize) {
+                sched.probe_now(ProbeRequest::Host(host.name.clone()));
+            }
+        }
+    });
+
+    let sched = scheduler.clone();
+    window.on_probe_all(move || {
+        sched.probe_now(ProbeRequest::All);
+    });
+
+    let manager = mgr.clone();
+    window.on_open_settings(move || {
+        let dialog = super::dialog::settings_dialog(manager.clone(), config.clone());
+        dialog.show().unwrap();
+    });
+
+    let manager = mgr.clone();
+    let sched = scheduler.clone();
+    window.on_toggle_pause(move || {
+        if !manager.write().unwrap().toggle_paused() {
+            sched.probe_now(ProbeRequest::All);
+        }
+    });
+
     window
 }
+
+/// Shows a file picker and waits for the choice. The portal backend used on
+/// Linux needs a tokio reactor, so one is provided for the duration.
+fn pick_file(picker: impl Future<Output = Option<FileHandle>>) -> Option<PathBuf> {
+    match Runtime::new() {
+        Ok(rt) => rt.block_on(picker).map(|file| file.path().to_path_buf()),
+        Err(err) => {
+            error!("failed to start the file picker: {err}");
+            None
+        }
+    }
+}
@@ -1,14 +1,82 @@
-use clap::{ArgAction, Parser};
+use std::path::PathBuf;
+
+use clap::{ArgAction, Parser, Subcommand};
 use log::LevelFilter;
 
+use crate::{export, import};
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Args {
     #[arg(short, long, action(ArgAction::Count))]
     pub verbose: u8,
+
+    /// Config file to use instead of the default one
+    #[arg(short, long, value_name = "PATH", conflicts_with = "profile")]
+    pub config: Option<PathBuf>,
+
+    /// Named profile with its own hosts and settings, e.g. "lab-a"
+    #[arg(short, long, value_name = "NAME", value_parser = parse_profile)]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Runs a one-off task on the config instead of starting the monitor.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Add hosts from a CSV file, an address list or a hosts file
+    Import {
+        file: PathBuf,
+
+        /// File format, guessed from the file name by default
+        #[arg(short, long, value_enum)]
+        format: Option<import::Format>,
+
+        /// Only show what would be added
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+    /// Sweep address ranges for responding hosts
+    Discover {
+        /// CIDR blocks, ranges like 10.0.0.10-50, or single addresses
+        #[arg(required = true)]
+        range: Vec<String>,
+
+        /// TCP ports to try, instead of the configured ones
+        #[arg(long, value_delimiter = ',')]
+        ports: Option<Vec<u16>>,
+
+        /// Add the hosts found instead of only listing them
+        #[arg(long)]
+        add: bool,
+    },
+    /// Probe all hosts once and write their status as CSV, JSON or Markdown
+    Export {
+        /// Output file, or "-" for standard output
+        file: PathBuf,
+
+        /// File format, guessed from the extension by default
+        #[arg(short, long, value_enum)]
+        format: Option<export::Format>,
+    },
 }
 
 impl Args {
+    /// The config file selected by `--config` or `--profile`.
+    pub fn config_path(&self) -> PathBuf {
+        if let Some(config) = &self.config {
+            return config.clone();
+        }
+        let mut path = dirs::config_dir().expect("config_dir should be valid");
+        match &self.profile {
+            Some(profile) => path.push(format!("monitorhosts-{profile}.json")),
+            None => path.push("monitorhosts.json"),
+        }
+        path
+    }
+
     pub fn log_level(&self) -> LevelFilter {
         match self.verbose {
             0 => LevelFilter::Warn,
@@ -18,3 +86,15 @@ impl Args {
         }
     }
 }
+
+fn parse_profile(name: &str) -> Result<String, String> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(name.to_owned())
+    } else {
+        Err("profile names may only contain letters, digits, '-' and '_'".to_owned())
+    }
+}
@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use log::info;
+
+/// Starts the monitor with the arguments it was started with when the user
+/// logs in, or stops doing so. Each config file gets its own entry, named
+/// after it, so that several profiles can start side by side.
+pub fn set(enabled: bool, config: &Path) -> Result<(), String> {
+    let name = config
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "monitorhosts".to_owned());
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    let mut command = vec![exe.to_string_lossy().into_owned()];
+    command.extend(std::env::args().skip(1));
+    info!("setting autostart of '{name}' to {enabled}: {command:?}");
+    register(&name, enabled, &command)
+}
+
+#[cfg(target_os = "windows")]
+fn register(name: &str, enabled: bool, command: &[String]) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    const KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    let line = quote(command);
+    let mut reg = Command::new("reg");
+    if enabled {
+        reg.args(["add", KEY, "/v", name, "/t", "REG_SZ", "/d", &line, "/f"]);
+    } else {
+        reg.args(["delete", KEY, "/v", name, "/f"]);
+    }
+    let output = reg
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|err| err.to_string())?;
+    // Deleting fails when the value is already gone, which is fine.
+    if enabled && !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn register(name: &str, enabled: bool, command: &[String]) -> Result<(), String> {
+    let path = dirs::home_dir()
+        .ok_or("找不到用户目录")?
+        .join("Library/LaunchAgents")
+        .join(format!("{name}.plist"));
+    if !enabled {
+        return remove(&path);
+    }
+    let arguments: String = command
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", escape(arg)))
+        .collect();
+    let content = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        escape(name)
+    );
+    write(&path, &content)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn register(name: &str, enabled: bool, command: &[String]) -> Result<(), String> {
+    let path = dirs::config_dir()
+        .ok_or("找不到配置目录")?
+        .join("autostart")
+        .join(format!("{name}.desktop"));
+    if !enabled {
+        return remove(&path);
+    }
+    let content = format!(
+        "[Desktop Entry]\nType=Application\nName=MonitorHosts\nExec={}\nX-GNOME-Autostart-enabled=true\n",
+        quote(command)
+    );
+    write(&path, &content)
+}
+
+/// Joins `command` into one line, quoting every part.
+#[cfg(not(target_os = "macos"))]
+fn quote(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|arg| format!("\"{}\"", arg.replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(target_os = "macos")]
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(not(target_os = "windows"))]
+fn write(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    }
+    std::fs::write(path, content).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn remove(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("{}: {err}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+
+use log::info;
+
+use crate::args::Command;
+use crate::config;
+use crate::controllers::discovery::{self, Found};
+use crate::controllers::monitor;
+use crate::export;
+use crate::import::{self, Duplicate};
+use crate::manager::{HostConfig, Manager};
+
+/// Runs `command` against the config at `path`. A running instance picks the
+/// changes up when it notices the file changed.
+pub fn run(path: &Path, command: &Command) -> Result<(), String> {
+    match command {
+        Command::Import {
+            file,
+            format,
+            dry_run,
+        } => {
+            let hosts = import::read(file, *format).map_err(|err| format!("{file:?}: {err}"))?;
+            add(path, hosts, *dry_run)
+        }
+        Command::Export { file, format } => {
+            let document = config::read(path)?.ok_or_else(|| format!("no hosts in {path:?}"))?;
+            let manager = Arc::new(RwLock::new(Manager::with_document(document)));
+            monitor::probe_once(&manager);
+            let snapshot = manager.read().unwrap().snapshot();
+            if file.as_os_str() == "-" {
+                let format = format.unwrap_or(export::Format::Markdown);
+                print!("{}", export::render(&snapshot, format)?);
+            } else {
+                export::write(file, &snapshot, *format)
+                    .map_err(|err| format!("{file:?}: {err}"))?;
+            }
+            Ok(())
+        }
+        Command::Discover {
+            range,
+            ports,
+            add: save,
+        } => {
+            let targets = discovery::parse_range(&range.join(" "))?;
+            let document = config::read(path)?.unwrap_or_default();
+            let mut settings = document.settings.discovery;
+            if let Some(ports) = ports {
+                settings.ports = ports.clone();
+            }
+            eprintln!("sweeping {} addresses...", targets.len());
+            let found = Arc::new(Mutex::new(Vec::new()));
+            let results = found.clone();
+            let report = Arc::new(move |_, result: Option<Found>| {
+                if let Some(result) = result {
+                    results.lock().unwrap().push(result);
+                }
+            });
+            discovery::sweep(targets, &settings, Arc::default(), report);
+            let mut found = found.lock().unwrap().clone();
+            found.sort_by_key(|found| found.addr);
+            let hosts = found.iter().map(Found::host).collect();
+            add(path, hosts, !save)
+        }
+    }
+}
+
+/// Prints what would be added, and adds it unless `dry_run` is set.
+fn add(path: &Path, hosts: Vec<HostConfig>, dry_run: bool) -> Result<(), String> {
+    let mut document = config::read(path)?.unwrap_or_default();
+    let candidates = import::preview(hosts, &document.hosts);
+    let mut added = 0;
+    for candidate in candidates {
+        let (mark, note) = match candidate.duplicate {
+            None => ("+", ""),
+            Some(Duplicate::Existing) => ("=", " (already monitored)"),
+            Some(Duplicate::Repeated) => ("=", " (repeated)"),
+        };
+        println!(
+            "{mark} {:<40} {:<20} {}{note}",
+            candidate.host.name,
+            candidate.host.label.as_deref().unwrap_or_default(),
+            candidate.describe(),
+        );
+        if candidate.duplicate.is_none() {
+            document.hosts.push(candidate.host);
+            added += 1;
+        }
+    }
+    if dry_run {
+        println!("{added} hosts would be added");
+        return Ok(());
+    }
+    if added > 0 {
+        let backups = document.settings.persistence.backups;
+        config::save(path, &document, backups).map_err(|err| err.to_string())?;
+        info!("saved {added} new hosts to '{path:?}'");
+    }
+    println!("{added} hosts added");
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::manager::{GroupConfig, HostConfig};
+use crate::settings::Settings;
+
+/// Version written by this build; documents are migrated up to it on load.
+pub const VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` turns a version `n` document into a version `n + 1` one.
+const MIGRATIONS: [fn(Value) -> Result<Value, String>; 1] = [from_legacy];
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Document {
+    pub version: u32,
+    #[serde(default)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub hosts: Vec<HostConfig>,
+    #[serde(default)]
+    pub groups: Vec<GroupConfig>,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            settings: Settings::default(),
+            hosts: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+}
+
+/// Loads the document at `path`, migrating older formats. A file that cannot
+/// be understood is kept aside as a backup and an empty document is returned,
+/// so that saving later does not destroy it.
+pub fn load(path: &Path) -> Option<Document> {
+    match read(path) {
+        Ok(Some(document)) => Some(document),
+        Ok(None) => {
+            warn!("config file '{path:?}' not exists");
+            None
+        }
+        Err(err) => {
+            error!("{err}");
+            backup_broken(path);
+            None
+        }
+    }
+}
+
+/// Reads the document at `path` like `load`, but leaves a broken file alone
+/// and reports why it could not be used. `None` if there is no file.
+pub fn read(path: &Path) -> Result<Option<Document>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read file '{path:?}': {err}"))?;
+    parse(&content)
+        .map(Some)
+        .map_err(|err| format!("failed deserialize '{path:?}': {err}"))
+}
+
+pub fn parse(content: &str) -> Result<Document, String> {
+    let mut value: Value = serde_json::from_str(content).map_err(|err| err.to_string())?;
+    let mut version = match &value {
+        // Before versioning the file was a bare list of hosts.
+        Value::Array(_) => 0,
+        Value::Object(object) => object
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or("missing 'version'")? as u32,
+        _ => return Err("expected an object or an array".to_owned()),
+    };
+    if version > VERSION {
+        return Err(format!(
+            "version {version} is newer than the supported {VERSION}"
+        ));
+    }
+    while version < VERSION {
+        info!("migrating config from version {version}...");
+        value = MIGRATIONS[version as usize](value)?;
+        version += 1;
+    }
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
+
+/// Writes `document` to a temporary file and renames it over `path`, so
+/// that a crash never leaves a half written config behind. The previous
+/// `backups` versions are kept as `path.1`, `path.2`, ...
+///
+/// Returns the content written.
+pub fn save(path: &Path, document: &Document, backups: usize) -> std::io::Result<String> {
+    let content = serde_json::to_string_pretty(document)?;
+    let temp = sibling(path, "tmp");
+    std::fs::write(&temp, &content)?;
+    if backups > 0 && path.exists() {
+        for n in (1..backups).rev() {
+            let older = sibling(path, &n.to_string());
+            if older.exists() {
+                std::fs::rename(&older, sibling(path, &(n + 1).to_string()))?;
+            }
+        }
+        std::fs::copy(path, sibling(path, "1"))?;
+    }
+    std::fs::rename(&temp, path)?;
+    Ok(content)
+}
+
+/// `path` with `suffix` appended to its file name.
+pub fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn from_legacy(hosts: Value) -> Result<Value, String> {
+    Ok(json!({
+        "version": 1,
+        "hosts": hosts,
+    }))
+}
+
+/// Copies `path` aside as `path.broken-<time>` before it gets overwritten.
+pub fn backup_broken(path: &Path) {
+    let suffix = Local::now().format("broken-%Y%m%d-%H%M%S").to_string();
+    let backup = sibling(path, &suffix);
+    match std::fs::copy(path, &backup) {
+        Ok(_) => warn!("kept unreadable config '{path:?}' as '{backup:?}'"),
+        Err(err) => error!("failed to back up '{path:?}' to '{backup:?}': {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_host_list_is_migrated() {
+        let document = parse(r#"[{"name": "bmc1", "ports": {"Ipmi": true}}]"#).unwrap();
+        assert_eq!(document.version, VERSION);
+        assert_eq!(document.hosts.len(), 1);
+        assert_eq!(document.hosts[0].name, "bmc1");
+        assert!(document.groups.is_empty());
+    }
+
+    #[test]
+    fn every_version_has_a_migration() {
+        assert_eq!(MIGRATIONS.len(), VERSION as usize);
+    }
+
+    #[test]
+    fn current_document_is_kept() {
+        let document = parse(r#"{"version": 1, "groups": [{"name": "rack1"}]}"#).unwrap();
+        assert!(document.hosts.is_empty());
+        assert_eq!(document.groups[0].name, "rack1");
+        assert_eq!(document.settings.probe.interval_secs, 10);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let err = parse(r#"{"version": 99}"#).unwrap_err();
+        assert!(err.contains("newer"), "{err}");
+    }
+
+    #[test]
+    fn malformed_documents_are_rejected() {
+        assert!(parse(r#"{"hosts": []}"#).is_err());
+        assert!(parse(r#"{"version": "1"}"#).is_err());
+        assert!(parse("42").is_err());
+        assert!(parse("[{\"name\": ").is_err());
+        assert!(parse(r#"[{"ports": {}}]"#).is_err());
+    }
+
+    #[test]
+    fn save_keeps_rolling_backups() {
+        let dir = std::env::temp_dir().join(format!("monitorhosts-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("monitorhosts.json");
+        for interval in 1..=3 {
+            let mut document = Document::default();
+            document.settings.probe.interval_secs = interval;
+            save(&path, &document, 2).unwrap();
+        }
+        let interval = |path: &Path| {
+            let content = std::fs::read_to_string(path).unwrap();
+            parse(&content).unwrap().settings.probe.interval_secs
+        };
+        assert_eq!(interval(&path), 3);
+        assert_eq!(interval(&sibling(&path, "1")), 2);
+        assert_eq!(interval(&sibling(&path, "2")), 1);
+        assert!(!sibling(&path, "3").exists());
+        assert!(!sibling(&path, "tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn read_reports_broken_files_and_ignores_missing_ones() {
+        let dir = std::env::temp_dir().join(format!("monitorhosts-read-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("monitorhosts.json");
+        assert!(read(&path).unwrap().is_none());
+        std::fs::write(&path, "{").unwrap();
+        assert!(read(&path).is_err());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use log::{trace, warn};
+use surge_ping::{Client, PingIdentifier, PingSequence, ICMP};
+use tokio::{
+    net::TcpStream,
+    runtime::Runtime,
+    sync::Semaphore,
+    task::JoinSet,
+    time::{self, Instant},
+};
+
+use super::liveness::icmp_client;
+use super::monitor::resolve;
+use crate::{
+    manager::HostConfig,
+    settings::{DetectionSettings, DiscoverySettings},
+};
+
+/// Largest number of addresses a single sweep may cover.
+const MAX_TARGETS: u128 = 65536;
+
+/// An address that answered a ping or a connection attempt.
+#[derive(Clone, Debug)]
+pub struct Found {
+    pub addr: IpAddr,
+    pub ping: bool,
+    /// Ports that accepted a connection.
+    pub ports: Vec<u16>,
+}
+
+impl Found {
+    /// A host checking only what was found to answer.
+    pub fn host(&self) -> HostConfig {
+        HostConfig::with_ports(self.addr.to_string(), &self.ports)
+    }
+}
+
+/// Called with the number of addresses done so far, and what was found on
+/// the one just finished.
+pub type Report = dyn Fn(usize, Option<Found>) + Send + Sync;
+
+/// Expands `10.0.0.0/24`, `10.0.0.10-10.0.0.20`, `10.0.0.10-20` or single
+/// addresses, separated by commas or spaces.
+pub fn parse_range(spec: &str) -> Result<Vec<IpAddr>, String> {
+    let mut targets = Vec::new();
+    for part in spec.split([',', ' ', '\t', '\n']).filter(|p| !p.is_empty()) {
+        let (first, last) = if let Some((addr, prefix)) = part.split_once('/') {
+            let addr: IpAddr = addr.parse().map_err(|_| format!("无效的地址 '{addr}'"))?;
+            let bits = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix: u32 = match prefix.parse() {
+                Ok(prefix) if prefix <= bits => prefix,
+                _ => return Err(format!("无效的前缀长度 '/{prefix}'")),
+            };
+            let all = u128::MAX >> (128 - bits);
+            let host = all.checked_shr(prefix).unwrap_or(0);
+            let network = number(addr) & !host;
+            let broadcast = network | host;
+            // The network and broadcast addresses of IPv4 subnets are not hosts.
+            if addr.is_ipv4() && prefix <= 30 {
+                (network + 1, broadcast - 1)
+            } else {
+                (network, broadcast)
+            }
+        } else if let Some((start, end)) = part.split_once('-') {
+            let start: IpAddr = start.parse().map_err(|_| format!("无效的地址 '{start}'"))?;
+            let end = match (end.parse::<IpAddr>(), end.parse::<u8>(), start) {
+                (Ok(end), _, _) if end.is_ipv4() == start.is_ipv4() => end,
+                (_, Ok(last), IpAddr::V4(start)) => {
+                    let [a, b, c, _] = start.octets();
+                    IpAddr::V4(Ipv4Addr::new(a, b, c, last))
+                }
+                _ => return Err(format!("无效的地址范围 '{part}'")),
+            };
+            (number(start), number(end))
+        } else {
+            let addr: IpAddr = part.parse().map_err(|_| format!("无效的地址 '{part}'"))?;
+            (number(addr), number(addr))
+        };
+        if last < first {
+            return Err(format!("地址范围 '{part}' 为空"));
+        }
+        let count = (last - first)
+            .checked_add(1)
+            .and_then(|count| count.checked_add(targets.len() as u128));
+        if count.is_none_or(|count| count > MAX_TARGETS) {
+            return Err(format!("一次最多扫描 {MAX_TARGETS} 个地址"));
+        }
+        let v4 = !part.contains(':');
+        targets.extend((first..=last).map(|n| address(n, v4)));
+    }
+    if targets.is_empty() {
+        return Err("请输入要扫描的地址范围".to_owned());
+    }
+    Ok(targets)
+}
+
+/// Pings and connects to the configured ports of every target, a few at a
+/// time and no faster than the configured rate, until done or `cancel` is
+/// set. Blocks the calling thread.
+pub fn sweep(
+    targets: Vec<IpAddr>,
+    settings: &DiscoverySettings,
+    cancel: Arc<AtomicBool>,
+    report: Arc<Report>,
+) {
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async move {
+        let interval = match settings.rate {
+            0 => Duration::ZERO,
+            rate => Duration::from_secs(1) / rate,
+        };
+        let sweeper = Arc::new(Sweeper {
+            v4: icmp_client(ICMP::V4).ok(),
+            v6: icmp_client(ICMP::V6).ok(),
+            ports: settings.ports.clone(),
+            timeout: Duration::from_millis(settings.timeout_ms),
+            interval,
+            next: Mutex::new(Instant::now()),
+            done: AtomicUsize::new(0),
+            cancel,
+            report,
+        });
+        let semaphore = Arc::new(Semaphore::new(settings.concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        for addr in targets {
+            let permit = semaphore.clone().acquire_owned().await.unwrap();
+            if sweeper.cancel.load(Ordering::Relaxed) {
+                trace!("sweep cancelled");
+                break;
+            }
+            let sweeper = sweeper.clone();
+            tasks.spawn(async move {
+                let found = sweeper.probe(addr).await;
+                drop(permit);
+                let done = sweeper.done.fetch_add(1, Ordering::Relaxed) + 1;
+                (sweeper.report)(done, found);
+            });
+        }
+        while let Some(result) = tasks.join_next().await {
+            if let Err(err) = result {
+                warn!("sweep task failed: {err}");
+            }
+        }
+    });
+}
+
+/// Ports of `host` among the configured ones that accept a connection, all
+/// tried at once. Blocks the calling thread.
+pub fn detect(host: &str, settings: &DetectionSettings) -> Result<Vec<u16>, String> {
+    let addr = resolve(host, 0).map_err(|err| err.to_string())?.ip();
+    let timeout = Duration::from_millis(settings.timeout_ms);
+    let rt = Runtime::new().unwrap();
+    let mut open: Vec<u16> = rt.block_on(async {
+        let mut tasks = JoinSet::new();
+        for port in settings.ports.clone() {
+            tasks.spawn(async move {
+                let connect = time::timeout(timeout, TcpStream::connect((addr, port))).await;
+                matches!(connect, Ok(Ok(_))).then_some(port)
+            });
+        }
+        let mut open = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            match result {
+                Ok(Some(port)) => open.push(port),
+                Ok(None) => {}
+                Err(err) => warn!("detect task failed: {err}"),
+            }
+        }
+        open
+    });
+    open.sort();
+    trace!("detected open ports on '{host}': {open:?}");
+    Ok(open)
+}
+
+struct Sweeper {
+    v4: Option<Client>,
+    v6: Option<Client>,
+    ports: Vec<u16>,
+    timeout: Duration,
+    /// Minimum time between two probes, for rate limiting.
+    interval: Duration,
+    next: Mutex<Instant>,
+    done: AtomicUsize,
+    cancel: Arc<AtomicBool>,
+    report: Arc<Report>,
+}
+
+impl Sweeper {
+    async fn probe(&self, addr: IpAddr) -> Option<Found> {
+        let client = match addr {
+            IpAddr::V4(_) => &self.v4,
+            IpAddr::V6(_) => &self.v6,
+        };
+        let mut ping = false;
+        if let Some(client) = client {
+            self.pace().await;
+            let mut pinger = client.pinger(addr, PingIdentifier(rand::random())).await;
+            pinger.timeout(self.timeout);
+            ping = pinger.ping(PingSequence(0), &[0; 56]).await.is_ok();
+        }
+        // A refused connection still proves the host is there.
+        let mut refused = false;
+        let mut ports = Vec::new();
+        for port in &self.ports {
+            if self.cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            self.pace().await;
+            match time::timeout(self.timeout, TcpStream::connect((addr, *port))).await {
+                Ok(Ok(_)) => ports.push(*port),
+                Ok(Err(err)) if err.kind() == ErrorKind::ConnectionRefused => refused = true,
+                Ok(Err(err)) => trace!("sweep '{addr}:{port}': {err}"),
+                Err(_) => {}
+            }
+        }
+        (ping || refused || !ports.is_empty()).then_some(Found { addr, ping, ports })
+    }
+
+    /// Waits for the next free slot under the rate limit.
+    async fn pace(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        time::sleep_until(slot).await;
+    }
+}
+
+fn number(addr: IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(addr) => u32::from(addr) as u128,
+        IpAddr::V6(addr) => u128::from(addr),
+    }
+}
+
+fn address(number: u128, v4: bool) -> IpAddr {
+    if v4 {
+        IpAddr::V4(Ipv4Addr::from(number as u32))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(spec: &str) -> Vec<String> {
+        parse_range(spec)
+            .unwrap()
+            .iter()
+            .map(IpAddr::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn ipv4_subnet_skips_network_and_broadcast() {
+        let targets = strings("10.0.0.0/30");
+        assert_eq!(targets, vec!["10.0.0.1", "10.0.0.2"]);
+        assert_eq!(parse_range("10.0.0.7/24").unwrap().len(), 254);
+        assert_eq!(strings("10.0.0.4/31"), vec!["10.0.0.4", "10.0.0.5"]);
+        assert_eq!(strings("10.0.0.9/32"), vec!["10.0.0.9"]);
+    }
+
+    #[test]
+    fn ranges_and_lists() {
+        assert_eq!(
+            strings("10.0.0.254-10.0.1.1"),
+            vec!["10.0.0.254", "10.0.0.255", "10.0.1.0", "10.0.1.1"]
+        );
+        assert_eq!(
+            strings("10.0.0.10-12"),
+            vec!["10.0.0.10", "10.0.0.11", "10.0.0.12"]
+        );
+        assert_eq!(
+            strings("10.0.0.1, 10.0.0.3\tfe80::1"),
+            vec!["10.0.0.1", "10.0.0.3", "fe80::1"]
+        );
+        assert_eq!(strings("fe80::1-fe80::2"), vec!["fe80::1", "fe80::2"]);
+        assert_eq!(strings("fe80::/127"), vec!["fe80::", "fe80::1"]);
+    }
+
+    #[test]
+    fn invalid_ranges_are_rejected() {
+        assert!(parse_range("").is_err());
+        assert!(parse_range("10.0.0.5-2").is_err());
+        assert!(parse_range("10.0.0.1-fe80::1").is_err());
+        assert!(parse_range("10.0.0.0/33").is_err());
+        assert!(parse_range("10.0.0.1-300").is_err());
+        assert!(parse_range("host.example").is_err());
+    }
+
+    #[test]
+    fn huge_ranges_are_rejected_without_overflow() {
+        assert!(parse_range("10.0.0.0/15").is_err());
+        assert_eq!(parse_range("10.0.0.0/16").unwrap().len(), 65534);
+        assert!(parse_range("::/0").is_err());
+        assert!(parse_range("10.0.0.1 ::/0").is_err());
+        assert!(parse_range("::-ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff").is_err());
+        assert!(parse_range("10.0.0.0/16 10.1.0.0/29").is_err());
+    }
+}
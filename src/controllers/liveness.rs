@@ -0,0 +1,172 @@
+use std::{
+    io::{self, ErrorKind},
+    net::{IpAddr, SocketAddr},
+    sync::Mutex,
+    time::Duration,
+};
+
+use log::{error, info, trace, warn};
+use surge_ping::{Client, Config, PingIdentifier, PingSequence, SurgeError, ICMP};
+use tokio::net::{self, TcpStream};
+
+use crate::{
+    manager::{HostConfig, PingStats, PortStatus},
+    settings::LivenessSettings,
+};
+
+/// Ports tried by the TCP liveness check when the host has none enabled.
+const FALLBACK_PORTS: [u16; 5] = [22, 80, 443, 445, 3389];
+
+/// Checks whether hosts are alive, using ICMP echo when a socket can be opened
+/// and falling back to TCP connects otherwise.
+///
+/// Must be created inside a tokio runtime. Checks may run concurrently.
+pub struct Liveness {
+    v4: Mutex<Result<Client, String>>,
+    v6: Mutex<Result<Client, String>>,
+}
+
+impl Liveness {
+    pub fn new() -> Self {
+        Self {
+            v4: Mutex::new(icmp_client(ICMP::V4)),
+            v6: Mutex::new(icmp_client(ICMP::V6)),
+        }
+    }
+
+    /// Explains why ICMP is not used for some address family, if it is not.
+    pub fn notice(&self) -> Option<String> {
+        let reasons: Vec<String> = [("IPv4", &self.v4), ("IPv6", &self.v6)]
+            .into_iter()
+            .filter_map(|(family, client)| {
+                client
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .err()
+                    .map(|err| format!("{family} ICMP不可用（{err}）"))
+            })
+            .collect();
+        if reasons.is_empty() {
+            None
+        } else {
+            Some(format!("{}，已改用TCP端口探测主机存活", reasons.join("；")))
+        }
+    }
+
+    pub async fn check(
+        &self,
+        config: &HostConfig,
+        settings: &LivenessSettings,
+    ) -> (PortStatus, Option<PingStats>) {
+        let addr = match resolve(&config.name).await {
+            Ok(addr) => addr,
+            Err(e) => {
+                error!("{} resolve to ipaddr error: {}", config.name, e);
+                return (PortStatus::Error, None);
+            }
+        };
+        let client = match addr {
+            IpAddr::V4(_) => &self.v4,
+            IpAddr::V6(_) => &self.v6,
+        };
+        let icmp = client.lock().unwrap().clone();
+        if let Ok(icmp) = icmp {
+            match ping(&icmp, addr, settings).await {
+                Ok(result) => return result,
+                Err(err) => {
+                    warn!("ICMP is not permitted, falling back to TCP: {err}");
+                    *client.lock().unwrap() = Err(err.to_string());
+                }
+            }
+        }
+        (tcp_liveness(addr, config, settings).await, None)
+    }
+}
+
+/// Resolves `host` without blocking the runtime.
+async fn resolve(host: &str) -> io::Result<IpAddr> {
+    net::lookup_host((host, 0))
+        .await?
+        .next()
+        .map(|addr| addr.ip())
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("no address found for '{host}'"),
+            )
+        })
+}
+
+pub fn icmp_client(kind: ICMP) -> Result<Client, String> {
+    let config = Config::builder().kind(kind).build();
+    match Client::new(&config) {
+        Ok(client) => {
+            info!(
+                "using {:?} socket for {kind:?} ICMP",
+                client.get_socket().get_type()
+            );
+            Ok(client)
+        }
+        Err(err) => {
+            warn!("failed to open {kind:?} ICMP socket: {err}");
+            Err(err.to_string())
+        }
+    }
+}
+
+/// Sends a burst of echo requests. Fails only if the socket turns out to be
+/// unusable, e.g. because sending is not permitted.
+async fn ping(
+    client: &Client,
+    addr: IpAddr,
+    settings: &LivenessSettings,
+) -> io::Result<(PortStatus, Option<PingStats>)> {
+    let payload = [0; 56];
+    let mut pinger = client.pinger(addr, PingIdentifier(rand::random())).await;
+    pinger.timeout(Duration::from_millis(settings.timeout_ms));
+    let count = settings.count.max(1);
+    let mut rtts = Vec::new();
+    for seq in 0..count {
+        if seq > 0 {
+            tokio::time::sleep(Duration::from_millis(settings.interval_ms)).await;
+        }
+        match pinger.ping(PingSequence(seq), &payload).await {
+            Ok((_, rtt)) => rtts.push(rtt),
+            Err(SurgeError::IOError(err)) if err.kind() == ErrorKind::PermissionDenied => {
+                return Err(err);
+            }
+            Err(err) => warn!("ping '{}' (seq {seq}) error: {}", pinger.host, err),
+        }
+    }
+    let stats = PingStats::new(count, &rtts);
+    Ok((stats.status(settings), Some(stats)))
+}
+
+/// A host answering a connect with either SYN-ACK or RST is alive.
+async fn tcp_liveness(
+    addr: IpAddr,
+    config: &HostConfig,
+    settings: &LivenessSettings,
+) -> PortStatus {
+    let mut ports: Vec<u16> = config
+        .ports
+        .iter()
+        .filter(|(_, enabled)| **enabled)
+        .map(|(port, _)| config.port_number(port))
+        .collect();
+    if ports.is_empty() {
+        ports = FALLBACK_PORTS.to_vec();
+    }
+    let timeout = Duration::from_millis(settings.timeout_ms);
+    for port in ports {
+        let connect = TcpStream::connect(SocketAddr::new(addr, port));
+        match tokio::time::timeout(timeout, connect).await {
+            Ok(Ok(_)) => return PortStatus::On,
+            Ok(Err(err)) if err.kind() == ErrorKind::ConnectionRefused => return PortStatus::On,
+            Ok(Err(err)) => trace!("tcp liveness '{addr}:{port}': {err}"),
+            Err(_) => trace!("tcp liveness '{addr}:{port}': timed out"),
+        }
+    }
+    PortStatus::Off
+}
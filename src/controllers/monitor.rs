@@ -1,19 +1,25 @@
-use std::net::TcpStream;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
 use std::{
-    net::IpAddr,
+    io::{self, ErrorKind},
+    net::SocketAddr,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc, RwLock,
     },
     thread::{self, JoinHandle},
     time::Duration,
 };
 
-use surge_ping::{Client, Config, PingIdentifier, PingSequence, ICMP};
-use tokio::runtime::Runtime;
+use tokio::{runtime::Runtime, task::JoinSet};
 
+use super::liveness::Liveness;
+use super::scheduler::{ProbeRequest, Scheduler};
+use crate::app::theme;
 use crate::{
-    manager::{Manager, PortStatus, Snapshot},
+    maintenance::MaintenanceMode,
+    manager::{Check, HostConfig, Manager, PortStatus, Snapshot},
+    notifier,
+    service::{Payload, Service, ServiceKind},
     ui::*,
 };
 use log::{error, trace, warn};
@@ -25,7 +31,11 @@ pub struct Monitor {
 }
 
 impl Monitor {
-    pub fn new(manager: Arc<RwLock<Manager>>, window: &MainWindow) -> Self {
+    pub fn new(
+        manager: Arc<RwLock<Manager>>,
+        scheduler: Arc<Scheduler>,
+        window: &MainWindow,
+    ) -> Self {
         let terminate_flag = Arc::new(AtomicBool::new(false));
         let mut threads = Vec::new();
 
@@ -44,57 +54,27 @@ impl Monitor {
 
         let flag = terminate_flag.clone();
         let mgr = manager.clone();
-        threads.push(thread::spawn(move || loop {
-            if flag.load(Ordering::Relaxed) {
-                break;
+        let mut ticker = scheduler.subscribe();
+        threads.push(thread::spawn(move || {
+            while let Some(request) = ticker.wait(interval(&mgr), &flag) {
+                mgr.write().unwrap().refresh_maintenance();
+                let hosts = targets(&mgr, &request);
+                probe_all_ports(&mgr, &hosts);
             }
-            let hosts = mgr.read().unwrap().hosts.clone();
-            hosts.iter().for_each(|config| {
-                config.ports.iter().for_each(|(port, enabled)| {
-                    if *enabled {
-                        let status = tcping(&config.name, port.u16());
-                        mgr.write().unwrap().update(
-                            config.name.to_owned(),
-                            port.to_owned(),
-                            status,
-                        );
-                    }
-                });
-            });
-
-            thread::sleep(Duration::from_secs(10));
         }));
 
         let flag = terminate_flag.clone();
         let mgr = manager.clone();
+        let mut ticker = scheduler.subscribe();
         let rt = Runtime::new().unwrap();
         threads.push(thread::spawn(move || {
             rt.block_on(async {
-                let client_v4 = Client::new(&Config::default())
-                    .expect("surge-ping Config for IPv4 should be created successfully");
-                let client_v6 = Client::new(&Config::builder().kind(ICMP::V6).build())
-                    .expect("surge-ping Config for IPv6 should be created successfully");
-
-                loop {
-                    if flag.load(Ordering::Relaxed) {
-                        break;
-                    }
-                    let hosts = mgr.read().unwrap().hosts.clone();
-                    for config in hosts {
-                        let status = match config.name.parse() {
-                            Ok(IpAddr::V4(addr)) => ping(&client_v4, IpAddr::V4(addr)).await,
-                            Ok(IpAddr::V6(addr)) => ping(&client_v6, IpAddr::V6(addr)).await,
-                            Err(e) => {
-                                error!("{} parse to ipaddr error: {}", config.name, e);
-                                PortStatus::Error
-                            }
-                        };
-                        mgr.write()
-                            .unwrap()
-                            .update_liveness(config.name.to_owned(), status);
-                    }
+                let liveness = Arc::new(Liveness::new());
+                mgr.write().unwrap().set_liveness_notice(liveness.notice());
 
-                    thread::sleep(Duration::from_secs(10));
+                while let Some(request) = ticker.wait(interval(&mgr), &flag) {
+                    let hosts = targets(&mgr, &request);
+                    ping_all(&mgr, &liveness, hosts).await;
                 }
             });
         }));
@@ -115,14 +95,157 @@ impl Monitor {
     }
 }
 
+/// Probes every host once, for one-off runs without a `Monitor`.
+pub fn probe_once(manager: &Arc<RwLock<Manager>>) {
+    let hosts = targets(manager, &ProbeRequest::All);
+    probe_all_ports(manager, &hosts);
+    Runtime::new().unwrap().block_on(async {
+        let liveness = Arc::new(Liveness::new());
+        ping_all(manager, &liveness, hosts).await;
+    });
+}
+
+/// Checks the liveness of `hosts`, as many at a time as ports are probed.
+async fn ping_all(
+    manager: &Arc<RwLock<Manager>>,
+    liveness: &Arc<Liveness>,
+    hosts: Vec<HostConfig>,
+) {
+    let (settings, concurrency) = {
+        let m = manager.read().unwrap();
+        (
+            m.settings.liveness.clone(),
+            m.settings.probe.concurrency.max(1),
+        )
+    };
+    let settings = Arc::new(settings);
+    let mut pending = hosts.into_iter();
+    let mut running = JoinSet::new();
+    loop {
+        while running.len() < concurrency {
+            let Some(config) = pending.next() else {
+                break;
+            };
+            let (manager, liveness, settings) =
+                (manager.clone(), liveness.clone(), settings.clone());
+            running.spawn(async move {
+                let (status, stats) = liveness.check(&config, &settings).await;
+                let mut m = manager.write().unwrap();
+                m.update_liveness(config.name, status, stats);
+                m.set_liveness_notice(liveness.notice());
+            });
+        }
+        match running.join_next().await {
+            Some(Err(err)) => error!("liveness check failed: {err}"),
+            Some(Ok(())) => {}
+            None => break,
+        }
+    }
+}
+
+/// Time until the next round, re-read every round so changes apply live.
+fn interval(manager: &RwLock<Manager>) -> Duration {
+    manager.read().unwrap().settings.probe.interval()
+}
+
+/// Probes the ports and services of `hosts`, a few hosts at a time.
+fn probe_all_ports(manager: &RwLock<Manager>, hosts: &[HostConfig]) {
+    let (concurrency, timeout) = {
+        let settings = &manager.read().unwrap().settings.probe;
+        (
+            settings.concurrency.clamp(1, hosts.len().max(1)),
+            settings.timeout(),
+        )
+    };
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..concurrency {
+            scope.spawn(|| {
+                while let Some(config) = hosts.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    probe_ports(manager, config, timeout);
+                }
+            });
+        }
+    });
+}
+
+fn probe_ports(manager: &RwLock<Manager>, config: &HostConfig, timeout: Duration) {
+    // Resolved once per round, so that a slow name lookup is paid once and
+    // not by every check of the host.
+    let ip = match resolve(&config.name, 0) {
+        Ok(addr) => Some(addr.ip()),
+        Err(err) => {
+            error!("failed to resolve host '{}': {err}", config.name);
+            None
+        }
+    };
+    config.ports.iter().for_each(|(port, enabled)| {
+        if *enabled {
+            let status = match ip {
+                Some(ip) => tcping(SocketAddr::new(ip, config.port_number(port)), timeout),
+                None => PortStatus::Error,
+            };
+            manager.write().unwrap().update(
+                config.name.to_owned(),
+                Check::Port(port.to_owned()),
+                status,
+            );
+        }
+    });
+    config.services.iter().for_each(|service| {
+        let status = match ip {
+            Some(ip) => probe_service(SocketAddr::new(ip, service.port), service, timeout),
+            None => PortStatus::Error,
+        };
+        manager.write().unwrap().update(
+            config.name.to_owned(),
+            Check::Service(service.name.to_owned()),
+            status,
+        );
+    });
+}
+
+/// Hosts to probe for `request`; nothing while monitoring is paused.
+fn targets(manager: &RwLock<Manager>, request: &ProbeRequest) -> Vec<HostConfig> {
+    let m = manager.read().unwrap();
+    if m.paused() {
+        trace!("monitoring paused, skipping probe {request:?}");
+        return Vec::new();
+    }
+    let hosts = m.probe_targets();
+    match request {
+        ProbeRequest::All => hosts,
+        ProbeRequest::Host(name) => hosts
+            .into_iter()
+            .filter(|host| host.name == *name)
+            .collect(),
+    }
+}
+
 fn update(window: Weak<MainWindow>, manager: Arc<RwLock<Manager>>) {
-    let snapshot = manager.write().unwrap().capture();
+    let (snapshot, events, notifications, theme) = {
+        let mut m = manager.write().unwrap();
+        (
+            m.capture(),
+            m.take_events(),
+            m.settings.notifications.clone(),
+            m.settings.ui.theme,
+        )
+    };
+    if notifications.enabled {
+        notifier::notify(&events, &notifications);
+    }
+    let notice = snapshot.notices.join("\n");
+    let stale = snapshot.paused;
     window
         .upgrade_in_event_loop(move |window| {
             trace!("updating MainWindowAdapter...");
             let model = HostsStatusModel::from(snapshot).construct_tree_view_model();
             let adapter = window.global::<MainWindowAdapter>();
             adapter.set_model(model);
+            adapter.set_notice(notice.into());
+            adapter.set_stale(stale);
+            theme::apply(&window, theme);
             trace!("updating MainWindowAdapter...done");
         })
         .unwrap();
@@ -160,28 +283,65 @@ impl From<Snapshot> for HostsStatusModel {
             .iter()
             .map(|config| {
                 let name = config.name.to_owned();
-                let mut attrs = vec![name.clone()];
-                let liveness = match value.liveness.get(&name) {
+                let down_parent = value.unreachable.get(&name);
+                let maintenance = value.maintenance.get(&name);
+                let paused = matches!(maintenance, Some((MaintenanceMode::Pause, _)));
+                // Behind a dead parent, Off only means "cannot tell".
+                let show = |check: Check| match value.status.get(&(name.clone(), check)) {
+                    _ if paused => "⏸".to_string(),
+                    Some(status)
+                        if down_parent.is_some()
+                            && !status.flapping
+                            && matches!(status.status, PortStatus::Off | PortStatus::Degraded) =>
+                    {
+                        "⊘".to_string()
+                    }
                     Some(status) => status.to_string(),
                     None => "NA".to_string(),
                 };
-                attrs.push(liveness);
+                let title = match &config.label {
+                    Some(label) => std::format!("{label} ({name})"),
+                    None => name.clone(),
+                };
+                let mut attrs = match &config.group {
+                    Some(group) => vec![std::format!("{title} [{group}]")],
+                    None => vec![title],
+                };
+                attrs.push(show(Check::Ping));
+                let stats = match (maintenance, down_parent, value.ping_stats.get(&name)) {
+                    (Some((MaintenanceMode::Pause, until)), _, _) => {
+                        std::format!("维护暂停至 {}", until.format("%m-%d %H:%M"))
+                    }
+                    (Some((MaintenanceMode::Silence, until)), _, _) => {
+                        std::format!("告警静默至 {}", until.format("%m-%d %H:%M"))
+                    }
+                    (None, Some(parent), _) => std::format!("不可达（上级 {parent} 故障）"),
+                    (None, None, Some(stats)) => stats.to_string(),
+                    (None, None, None) => "".to_string(),
+                };
+                attrs.push(stats);
                 attrs.append(
                     &mut config
                         .ports
                         .iter()
                         .map(|(port, enabled)| {
                             if *enabled {
-                                match value.status.get(&(name.clone(), *port)) {
-                                    Some(status) => status.to_string(),
-                                    None => "NA".to_string(),
-                                }
+                                show(Check::Port(*port))
                             } else {
                                 "".to_string()
                             }
                         })
                         .collect(),
                 );
+                let services: Vec<String> = config
+                    .services
+                    .iter()
+                    .map(|service| {
+                        let status = show(Check::Service(service.name.clone()));
+                        std::format!("{} {status}", service.name)
+                    })
+                    .collect();
+                attrs.push(services.join("  "));
                 attrs
             })
             .collect();
@@ -189,31 +349,89 @@ impl From<Snapshot> for HostsStatusModel {
     }
 }
 
-fn tcping(host: &str, port: u16) -> PortStatus {
-    let target = std::format!("{host}:{port}");
-    match target.parse() {
-        Ok(addr) => match TcpStream::connect_timeout(&addr, Duration::from_secs(1)) {
-            Ok(_) => PortStatus::On,
-            Err(err) => {
-                error!("failed to connect '{target}': {err}");
-                PortStatus::Off
-            }
-        },
+/// Resolves `host`, an IP address or a hostname, to the first address found.
+/// Blocks the calling thread; async code uses `tokio::net::lookup_host`.
+pub fn resolve(host: &str, port: u16) -> io::Result<SocketAddr> {
+    (host, port).to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::NotFound,
+            std::format!("no address found for '{host}'"),
+        )
+    })
+}
+
+fn tcping(addr: SocketAddr, timeout: Duration) -> PortStatus {
+    match TcpStream::connect_timeout(&addr, timeout) {
+        Ok(_) => PortStatus::On,
         Err(err) => {
-            error!("failed to parse host '{target}': {err}");
-            PortStatus::Error
+            error!("failed to connect '{addr}': {err}");
+            PortStatus::Off
         }
     }
 }
 
-async fn ping(client: &Client, addr: IpAddr) -> PortStatus {
-    let payload = [0; 56];
-    let mut pinger = client.pinger(addr, PingIdentifier(rand::random())).await;
-    pinger.timeout(Duration::from_secs(1));
-    match pinger.ping(PingSequence(0), &payload).await {
-        Ok(_) => PortStatus::On,
+fn probe_service(addr: SocketAddr, service: &Service, timeout: Duration) -> PortStatus {
+    match &service.kind {
+        ServiceKind::Tcp => tcping(addr, timeout),
+        ServiceKind::Udp { payload, expect } => udping(addr, payload, expect.as_ref(), timeout),
+    }
+}
+
+fn udping(
+    addr: SocketAddr,
+    payload: &Payload,
+    expect: Option<&Payload>,
+    timeout: Duration,
+) -> PortStatus {
+    let data = match payload.bytes() {
+        Ok(data) => data,
+        Err(err) => {
+            error!("invalid payload for '{addr}': {err}");
+            return PortStatus::Error;
+        }
+    };
+    let bind = match addr {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    let socket = match UdpSocket::bind(bind) {
+        Ok(socket) => socket,
+        Err(err) => {
+            error!("failed to bind udp socket for '{addr}': {err}");
+            return PortStatus::Error;
+        }
+    };
+    if let Err(err) = socket
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| socket.connect(addr))
+        .and_then(|_| socket.send(&data))
+    {
+        error!("failed to send to '{addr}': {err}");
+        return PortStatus::Off;
+    }
+    let mut buf = [0; 1500];
+    match socket.recv(&mut buf) {
+        Ok(len) => match expect.map(|pattern| pattern.matches(&buf[..len])) {
+            None | Some(Ok(true)) => PortStatus::On,
+            Some(Ok(false)) => {
+                warn!("unexpected response from '{addr}'");
+                PortStatus::Off
+            }
+            Some(Err(err)) => {
+                error!("invalid expected response for '{addr}': {err}");
+                PortStatus::Error
+            }
+        },
+        Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            if expect.is_some() {
+                error!("no response from '{addr}'");
+                PortStatus::Off
+            } else {
+                PortStatus::On
+            }
+        }
         Err(err) => {
-            warn!("ping '{}' error: {}", pinger.host, err);
+            error!("failed to receive from '{addr}': {err}");
             PortStatus::Off
         }
     }
@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, RwLock,
+};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use log::{error, info, trace, warn};
+
+use crate::{config, manager::Manager};
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keeps the config file and the manager in sync: saves shortly after each
+/// change, and reloads the file when it is edited by someone else.
+pub struct Saver {
+    thread: JoinHandle<()>,
+    terminate_flag: Arc<AtomicBool>,
+}
+
+impl Saver {
+    pub fn new(manager: Arc<RwLock<Manager>>, path: PathBuf) -> Self {
+        let terminate_flag = Arc::new(AtomicBool::new(false));
+        let flag = terminate_flag.clone();
+        let thread = thread::spawn(move || {
+            let mut saved = manager.read().unwrap().revision();
+            let mut changed: Option<(u64, Instant)> = None;
+            // What the file contained when it was last loaded or saved.
+            let mut known = read(&path);
+            let mut watched = Instant::now();
+            loop {
+                let terminating = flag.load(Ordering::Relaxed);
+
+                if terminating || watched.elapsed() >= WATCH_INTERVAL {
+                    watched = Instant::now();
+                    let current = read(&path);
+                    if current != known {
+                        info!("config '{path:?}' changed on disk");
+                        if manager.read().unwrap().revision() != saved {
+                            keep_local_copy(&manager, &path);
+                        }
+                        if let Some(content) = &current {
+                            match config::parse(content) {
+                                Ok(document) => {
+                                    let mut m = manager.write().unwrap();
+                                    m.reload(document);
+                                    saved = m.revision();
+                                }
+                                Err(err) => {
+                                    // The next save replaces it, so keep the
+                                    // edit aside as `config::load` does.
+                                    error!("ignoring unreadable '{path:?}': {err}");
+                                    config::backup_broken(&path);
+                                }
+                            }
+                        }
+                        known = current;
+                        changed = None;
+                    }
+                }
+
+                let (revision, delay) = {
+                    let m = manager.read().unwrap();
+                    let delay = Duration::from_millis(m.settings.persistence.delay_ms);
+                    (m.revision(), delay)
+                };
+                if revision != saved {
+                    let since = match changed {
+                        Some((pending, since)) if pending == revision => since,
+                        _ => {
+                            let now = Instant::now();
+                            changed = Some((revision, now));
+                            now
+                        }
+                    };
+                    if terminating || since.elapsed() >= delay {
+                        if let Some((revision, content)) = save(&manager, &path) {
+                            saved = revision;
+                            known = Some(content);
+                        }
+                        changed = None;
+                    }
+                }
+                if terminating {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(200));
+            }
+        });
+        Self {
+            thread,
+            terminate_flag,
+        }
+    }
+
+    /// Stops the saver after writing any pending change.
+    pub fn join(self) {
+        trace!("wating saver to terminate...");
+        self.terminate_flag.store(true, Ordering::Relaxed);
+        self.thread.join().unwrap();
+        trace!("wating saver to terminate...done");
+    }
+}
+
+fn read(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+/// Saves the current document and returns the revision it reflects along
+/// with the content written.
+fn save(manager: &RwLock<Manager>, path: &Path) -> Option<(u64, String)> {
+    let (revision, document, backups) = {
+        let m = manager.read().unwrap();
+        (m.revision(), m.document(), m.settings.persistence.backups)
+    };
+    trace!("saving config revision {revision} to '{path:?}'...");
+    match config::save(path, &document, backups) {
+        Ok(content) => Some((revision, content)),
+        Err(err) => {
+            error!("failed to save config '{path:?}': {err}");
+            None
+        }
+    }
+}
+
+/// The file was edited while local changes were pending: the edit wins, and
+/// the local version is written next to it instead of being lost.
+fn keep_local_copy(manager: &RwLock<Manager>, path: &Path) {
+    let document = manager.read().unwrap().document();
+    let suffix = Local::now().format("local-%Y%m%d-%H%M%S").to_string();
+    let copy = config::sibling(path, &suffix);
+    match config::save(&copy, &document, 0) {
+        Ok(_) => warn!("config '{path:?}' edited concurrently, kept local changes as '{copy:?}'"),
+        Err(err) => error!("failed to keep local changes as '{copy:?}': {err}"),
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    Mutex,
+};
+use std::time::{Duration, Instant};
+
+use log::{trace, warn};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProbeRequest {
+    All,
+    Host(String),
+}
+
+/// Decides when the probe workers run: once per interval, and whenever a
+/// probe is requested out of cycle.
+pub struct Scheduler {
+    senders: Mutex<Vec<Sender<ProbeRequest>>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            senders: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Registers a worker; every later request is delivered to it.
+    pub fn subscribe(&self) -> Ticker {
+        let (tx, rx) = mpsc::channel();
+        self.senders.lock().unwrap().push(tx);
+        Ticker {
+            rx,
+            next: Instant::now(),
+        }
+    }
+
+    pub fn probe_now(&self, request: ProbeRequest) {
+        trace!("scheduling out-of-cycle probe {request:?}...");
+        self.senders
+            .lock()
+            .unwrap()
+            .retain(|tx| match tx.send(request.clone()) {
+                Ok(_) => true,
+                Err(err) => {
+                    warn!("dropping terminated probe worker: {err}");
+                    false
+                }
+            });
+    }
+}
+
+pub struct Ticker {
+    rx: Receiver<ProbeRequest>,
+    next: Instant,
+}
+
+impl Ticker {
+    /// Blocks until the next cycle is due or a probe is requested, and
+    /// returns what to probe. Returns `None` once `terminate` is set.
+    pub fn wait(&mut self, interval: Duration, terminate: &AtomicBool) -> Option<ProbeRequest> {
+        loop {
+            if terminate.load(Ordering::Relaxed) {
+                return None;
+            }
+            let now = Instant::now();
+            if now >= self.next {
+                self.next = now + interval;
+                return Some(ProbeRequest::All);
+            }
+            let timeout = (self.next - now).min(Duration::from_secs(1));
+            match self.rx.recv_timeout(timeout) {
+                Ok(request) => return Some(request),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
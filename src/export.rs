@@ -0,0 +1,272 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::maintenance::MaintenanceMode;
+use crate::manager::{Check, HostConfig, Port, PortStatus, Snapshot};
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl Format {
+    /// Picks the format from the file extension, Markdown by default.
+    pub fn detect(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "csv" => Format::Csv,
+            "json" => Format::Json,
+            _ => Format::Markdown,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Report {
+    generated: DateTime<Local>,
+    paused: bool,
+    hosts: Vec<HostReport>,
+}
+
+#[derive(Serialize)]
+struct HostReport {
+    address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// Loss in percent and round trip times in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    loss: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jitter_ms: Option<f64>,
+    checks: Vec<CheckReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_change: Option<DateTime<Local>>,
+    /// Why the status may not reflect the host itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CheckReport {
+    check: String,
+    status: &'static str,
+    flapping: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<DateTime<Local>>,
+    #[serde(skip)]
+    key: Check,
+}
+
+impl HostReport {
+    fn new(snapshot: &Snapshot, config: &HostConfig) -> Self {
+        let name = &config.name;
+        let stats = snapshot.ping_stats.get(name);
+        let mut checks: Vec<Check> = vec![Check::Ping];
+        checks.extend(
+            config
+                .ports
+                .iter()
+                .filter(|(_, enabled)| **enabled)
+                .map(|(port, _)| Check::Port(*port)),
+        );
+        checks.extend(
+            config
+                .services
+                .iter()
+                .map(|service| Check::Service(service.name.clone())),
+        );
+        let checks: Vec<CheckReport> = checks
+            .into_iter()
+            .filter_map(|check| {
+                let status = snapshot.status.get(&(name.clone(), check.clone()))?;
+                Some(CheckReport {
+                    check: check.to_string(),
+                    status: word(status.status),
+                    flapping: status.flapping,
+                    since: status.since.map(local),
+                    key: check,
+                })
+            })
+            .collect();
+        let last_change = checks.iter().filter_map(|check| check.since).max();
+        let note = match (
+            snapshot.maintenance.get(name),
+            snapshot.unreachable.get(name),
+        ) {
+            (Some((MaintenanceMode::Pause, until)), _) => {
+                Some(format!("paused for maintenance until {}", time(until)))
+            }
+            (Some((MaintenanceMode::Silence, until)), _) => {
+                Some(format!("alerts silenced until {}", time(until)))
+            }
+            (None, Some(parent)) => Some(format!("unreachable, parent {parent} is down")),
+            (None, None) => None,
+        };
+        Self {
+            address: name.clone(),
+            label: config.label.clone(),
+            group: config.group.clone(),
+            tags: config.tags.clone(),
+            loss: stats.map(|stats| stats.loss()),
+            avg_ms: stats
+                .filter(|stats| stats.received > 0)
+                .map(|stats| millis(stats.avg)),
+            jitter_ms: stats
+                .filter(|stats| stats.received > 1)
+                .map(|stats| millis(stats.jitter)),
+            checks,
+            last_change,
+            note,
+        }
+    }
+
+    fn status(&self, check: &Check) -> String {
+        match self.checks.iter().find(|c| c.key == *check) {
+            Some(c) if c.flapping => "flapping".to_owned(),
+            Some(c) => c.status.to_owned(),
+            None => String::new(),
+        }
+    }
+
+    fn services(&self) -> String {
+        self.checks
+            .iter()
+            .filter(|c| matches!(c.key, Check::Service(_)))
+            .map(|c| format!("{} {}", c.check, self.status(&c.key)))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// One row of the CSV and Markdown tables, matching `COLUMNS`.
+    fn row(&self) -> Vec<String> {
+        let number = |value: Option<f64>| value.map(|v| format!("{v:.1}")).unwrap_or_default();
+        let mut row = vec![
+            self.address.clone(),
+            self.label.clone().unwrap_or_default(),
+            self.group.clone().unwrap_or_default(),
+            self.tags.join(" "),
+            self.status(&Check::Ping),
+            number(self.loss),
+            number(self.avg_ms),
+        ];
+        row.extend(
+            Port::ALL
+                .iter()
+                .map(|port| self.status(&Check::Port(*port))),
+        );
+        row.push(self.services());
+        row.push(self.last_change.as_ref().map(time).unwrap_or_default());
+        row.push(self.note.clone().unwrap_or_default());
+        row
+    }
+}
+
+const COLUMNS: [&str; 15] = [
+    "address",
+    "label",
+    "group",
+    "tags",
+    "ping",
+    "loss_%",
+    "avg_ms",
+    "http",
+    "https",
+    "ssh",
+    "vnc",
+    "ipmi",
+    "services",
+    "last_change",
+    "note",
+];
+
+/// Renders the hosts and their current status in `format`.
+pub fn render(snapshot: &Snapshot, format: Format) -> Result<String, String> {
+    let report = Report {
+        generated: Local::now(),
+        paused: snapshot.paused,
+        hosts: snapshot
+            .configs
+            .iter()
+            .map(|config| HostReport::new(snapshot, config))
+            .collect(),
+    };
+    match format {
+        Format::Json => serde_json::to_string_pretty(&report).map_err(|err| err.to_string()),
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer
+                .write_record(COLUMNS)
+                .map_err(|err| err.to_string())?;
+            for host in &report.hosts {
+                writer
+                    .write_record(host.row())
+                    .map_err(|err| err.to_string())?;
+            }
+            let bytes = writer.into_inner().map_err(|err| err.to_string())?;
+            String::from_utf8(bytes).map_err(|err| err.to_string())
+        }
+        Format::Markdown => {
+            let line = |cells: Vec<String>| {
+                let cells: Vec<String> = cells
+                    .into_iter()
+                    .map(|cell| cell.replace('|', "\\|"))
+                    .collect();
+                format!("| {} |\n", cells.join(" | "))
+            };
+            let mut text = format!("MonitorHosts {}", time(&report.generated));
+            if report.paused {
+                text.push_str(" (monitoring paused, status may be stale)");
+            }
+            text.push_str("\n\n");
+            text.push_str(&line(COLUMNS.iter().map(|c| c.to_string()).collect()));
+            text.push_str(&line(COLUMNS.iter().map(|_| "---".to_owned()).collect()));
+            for host in &report.hosts {
+                text.push_str(&line(host.row()));
+            }
+            Ok(text)
+        }
+    }
+}
+
+/// Writes the report to `path`, picking the format from its extension when
+/// none is given.
+pub fn write(path: &Path, snapshot: &Snapshot, format: Option<Format>) -> Result<(), String> {
+    let content = render(snapshot, format.unwrap_or_else(|| Format::detect(path)))?;
+    std::fs::write(path, content).map_err(|err| err.to_string())
+}
+
+fn word(status: PortStatus) -> &'static str {
+    match status {
+        PortStatus::On => "up",
+        PortStatus::Degraded => "degraded",
+        PortStatus::Off => "down",
+        PortStatus::Error => "error",
+    }
+}
+
+fn local(time: SystemTime) -> DateTime<Local> {
+    DateTime::from(time)
+}
+
+fn time(time: &DateTime<Local>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn millis(duration: std::time::Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
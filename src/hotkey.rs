@@ -1,80 +1,142 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
 
 use global_hotkey::HotKeyState;
-use global_hotkey::{
-    hotkey::{Code, HotKey, Modifiers},
-    GlobalHotKeyEvent, GlobalHotKeyManager,
-};
+use global_hotkey::{hotkey::HotKey, GlobalHotKeyEvent, GlobalHotKeyManager};
 use log::{error, info, trace, warn};
 use notify_rust::Notification;
-use url::Url;
+use slint::{ComponentHandle, Timer, TimerMode};
 
+use crate::app::dialog;
+use crate::controllers::discovery;
+use crate::controllers::scheduler::{ProbeRequest, Scheduler};
 use crate::manager::{HostConfig, Manager};
+use crate::settings::{DetectionSettings, HotkeySettings};
+use crate::ui::MainWindow;
+
+mod extract;
+
+use extract::{extract, Target};
+
+/// What a global hotkey does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Action {
+    AddHost,
+    ProbeAll,
+    ToggleWindow,
+    TogglePause,
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::AddHost => write!(f, "从剪贴板添加主机"),
+            Action::ProbeAll => write!(f, "全部检测"),
+            Action::ToggleWindow => write!(f, "显示/隐藏主界面"),
+            Action::TogglePause => write!(f, "暂停/恢复监控"),
+        }
+    }
+}
+
+/// Hotkeys currently registered, by id, shared with the worker thread.
+type Bound = Arc<Mutex<HashMap<u32, Action>>>;
 
-#[allow(dead_code)]
 pub struct HotkeyWorker {
     terminate_flag: Arc<AtomicBool>,
     thread: std::thread::JoinHandle<()>,
-    manager: GlobalHotKeyManager,
+    /// Re-registers the hotkeys when the settings change. Registration has
+    /// to happen on the thread that created the hotkey manager, which is the
+    /// one running the event loop.
+    _timer: Timer,
 }
 
 impl HotkeyWorker {
-    pub fn new(manager: Arc<RwLock<Manager>>) -> Self {
-        let mut clipboard = arboard::Clipboard::new().unwrap();
+    pub fn new(
+        manager: Arc<RwLock<Manager>>,
+        scheduler: Arc<Scheduler>,
+        window: &MainWindow,
+    ) -> Self {
         let terminate_flag = Arc::new(AtomicBool::new(false));
-        // initialize the hotkeys manager
-        let hotkey_manager = GlobalHotKeyManager::new().unwrap();
+        let bound: Bound = Arc::default();
 
-        // construct the hotkey
-        let modifiers = Modifiers::CONTROL | Modifiers::ALT | Modifiers::SHIFT;
-        let hotkey = HotKey::new(Some(modifiers), Code::KeyM);
-        info!("register hotkey: {hotkey:?}");
-        hotkey_manager
-            .register(hotkey)
-            .expect("hotkey {hotkey:?} should not be occupied");
+        let registry = match GlobalHotKeyManager::new() {
+            Ok(hotkey_manager) => Some(Registry {
+                manager: hotkey_manager,
+                registered: Vec::new(),
+                settings: None,
+                bound: bound.clone(),
+            }),
+            Err(err) => {
+                error!("failed to initialize global hotkeys: {err}");
+                manager
+                    .write()
+                    .unwrap()
+                    .set_hotkey_notice(Some(format!("全局快捷键不可用（{err}）")));
+                None
+            }
+        };
+        let timer = Timer::default();
+        if let Some(mut registry) = registry {
+            let mgr = manager.clone();
+            let mut sync = move || {
+                let settings = mgr.read().unwrap().settings.hotkeys.clone();
+                if registry.settings.as_ref() != Some(&settings) {
+                    let notice = registry.apply(settings);
+                    mgr.write().unwrap().set_hotkey_notice(notice);
+                }
+            };
+            sync();
+            timer.start(TimerMode::Repeated, Duration::from_secs(1), sync);
+        }
 
         let hotkey_channel = GlobalHotKeyEvent::receiver();
-
+        let window_weak = window.as_weak();
         let flag = terminate_flag.clone();
-
         let thread = thread::spawn(move || loop {
             if flag.load(Ordering::Relaxed) {
                 return;
             }
             if let Ok(event) = hotkey_channel.recv_timeout(Duration::from_secs(1)) {
                 trace!("hotkey({}) {:?}", event.id, event.state);
-                match event.state {
-                    HotKeyState::Pressed => {
-                        let text = clipboard.get_text().unwrap();
-                        trace!("Clipboard text was: {text}");
-                        let name = match extract(&text) {
-                            Some(name) => name,
-                            None => {
-                                warn!("extract: no valid ip found in {text}");
-                                continue;
+                if event.state != HotKeyState::Pressed {
+                    continue;
+                }
+                let action = bound.lock().unwrap().get(&event.id).copied();
+                match action {
+                    Some(Action::AddHost) => add_from_clipboard(&manager, &scheduler),
+                    Some(Action::ProbeAll) => scheduler.probe_now(ProbeRequest::All),
+                    Some(Action::ToggleWindow) => {
+                        let toggled = window_weak.upgrade_in_event_loop(|window| {
+                            let shown = if window.window().is_visible() {
+                                window.hide()
+                            } else {
+                                window.show()
+                            };
+                            if let Err(err) = shown {
+                                error!("failed to toggle main window: {err}");
                             }
-                        };
-                        let host = HostConfig::with_all_enable(name.clone());
-                        manager.write().unwrap().add_host(host);
-                        if let Err(err) = Notification::new()
-                            .summary("MonitorHosts")
-                            .body(&format!("添加主机：'{name}'"))
-                            .show()
-                        {
-                            error!("failed to show notification for adding host {name}: {err}");
+                        });
+                        if let Err(err) = toggled {
+                            error!("failed to toggle main window: {err}");
+                        }
+                    }
+                    Some(Action::TogglePause) => {
+                        if !manager.write().unwrap().toggle_paused() {
+                            scheduler.probe_now(ProbeRequest::All);
                         }
                     }
-                    HotKeyState::Released => {}
+                    None => warn!("unknown hotkey {}", event.id),
                 }
             }
         });
         Self {
             terminate_flag,
             thread,
-            manager: hotkey_manager,
+            _timer: timer,
         }
     }
 
@@ -86,14 +148,196 @@ impl HotkeyWorker {
     }
 }
 
-fn extract<S: std::fmt::Display>(text: S) -> Option<String> {
-    let name = format!("{text}");
-    let u = match Url::parse(&name) {
-        Ok(u) => u,
+struct Registry {
+    manager: GlobalHotKeyManager,
+    registered: Vec<HotKey>,
+    /// The settings last applied.
+    settings: Option<HotkeySettings>,
+    bound: Bound,
+}
+
+impl Registry {
+    /// Replaces the registered hotkeys with those in `settings`, returning
+    /// what could not be registered.
+    fn apply(&mut self, settings: HotkeySettings) -> Option<String> {
+        if let Err(err) = self.manager.unregister_all(&self.registered) {
+            warn!("failed to unregister hotkeys: {err}");
+        }
+        self.registered.clear();
+        let mut bound = self.bound.lock().unwrap();
+        bound.clear();
+
+        let mut failures = Vec::new();
+        let actions = [
+            (Action::AddHost, &settings.add_host),
+            (Action::ProbeAll, &settings.probe_all),
+            (Action::ToggleWindow, &settings.toggle_window),
+            (Action::TogglePause, &settings.toggle_pause),
+        ];
+        for (action, spec) in actions {
+            let Some(spec) = spec.as_deref().map(str::trim).filter(|s| !s.is_empty()) else {
+                continue;
+            };
+            let hotkey = match HotKey::from_str(spec) {
+                Ok(hotkey) => hotkey,
+                Err(err) => {
+                    warn!("invalid hotkey '{spec}' for {action:?}: {err}");
+                    failures.push(format!("{action}的快捷键 '{spec}' 无效"));
+                    continue;
+                }
+            };
+            info!("register hotkey {spec} for {action:?}");
+            match self.manager.register(hotkey) {
+                Ok(()) => {
+                    self.registered.push(hotkey);
+                    bound.insert(hotkey.id(), action);
+                }
+                Err(err) => {
+                    warn!("failed to register hotkey '{spec}' for {action:?}: {err}");
+                    failures.push(format!("{action}的快捷键 '{spec}' 无法注册，可能已被占用"));
+                }
+            }
+        }
+        self.settings = Some(settings);
+        if failures.is_empty() {
+            None
+        } else {
+            Some(failures.join("；"))
+        }
+    }
+}
+
+/// Adds what the clipboard contains: a single host directly or after
+/// confirming it, several hosts after picking them, and address ranges through a sweep.
+fn add_from_clipboard(manager: &Arc<RwLock<Manager>>, scheduler: &Arc<Scheduler>) {
+    let text = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+        Ok(text) => text,
         Err(err) => {
-            error!("failed to parse url '{name}': {err}");
-            return None;
+            warn!("failed to read the clipboard: {err}");
+            return;
         }
     };
-    u.host_str().map(ToOwned::to_owned)
+    trace!("Clipboard text was: {text}");
+    let targets = extract(&text);
+    if targets.is_empty() {
+        warn!(
+            "extract: no host found in {} characters of clipboard text",
+            text.chars().count()
+        );
+        notify("剪贴板中没有找到主机");
+        return;
+    }
+
+    let (settings, concurrency) = {
+        let m = manager.read().unwrap();
+        (
+            m.settings.detection.clone(),
+            m.settings.discovery.concurrency,
+        )
+    };
+    let mut ranges = Vec::new();
+    let mut found: Vec<Option<HostConfig>> = Vec::new();
+    let mut bare = Vec::new();
+    for target in targets {
+        match target {
+            Target::Host(host) => found.push(Some(host)),
+            Target::Bare(name) => {
+                bare.push((found.len(), name));
+                found.push(None);
+            }
+            Target::Range(range) => ranges.push(range),
+        }
+    }
+    for (index, host) in detect_all(&bare, &settings, concurrency) {
+        found[index] = Some(host);
+    }
+    let hosts: Vec<HostConfig> = found.into_iter().flatten().collect();
+
+    if !ranges.is_empty() {
+        let mgr = manager.clone();
+        let range = ranges.join(", ");
+        let shown = slint::invoke_from_event_loop(move || {
+            let dialog = dialog::discovery_dialog(mgr);
+            dialog.set_range(range.clone().into());
+            dialog.show().unwrap();
+            dialog.invoke_start_scan(range.into());
+        });
+        if let Err(err) = shown {
+            error!("failed to show discovery dialog: {err}");
+        }
+    }
+    match hosts.len() {
+        0 => {}
+        1 => {
+            let host = hosts.into_iter().next().unwrap();
+            let name = host.name.clone();
+            if manager.read().unwrap().contains_host(&name) {
+                notify(&format!("主机 '{name}' 已存在"));
+                return;
+            }
+            if manager.read().unwrap().settings.hotkeys.confirm_add {
+                let mgr = manager.clone();
+                let sched = scheduler.clone();
+                let shown = slint::invoke_from_event_loop(move || {
+                    dialog::confirm_add_dialog(mgr, sched, host).show().unwrap();
+                });
+                if let Err(err) = shown {
+                    error!("failed to show add dialog: {err}");
+                }
+                return;
+            }
+            manager.write().unwrap().add_host(host);
+            scheduler.probe_now(ProbeRequest::Host(name.clone()));
+            notify(&format!("添加主机：'{name}'"));
+        }
+        _ => {
+            let mgr = manager.clone();
+            let shown = slint::invoke_from_event_loop(move || {
+                dialog::import_dialog(mgr, "剪贴板", Ok(hosts))
+                    .show()
+                    .unwrap();
+            });
+            if let Err(err) = shown {
+                error!("failed to show import dialog: {err}");
+            }
+        }
+    }
+}
+
+/// Detects the services of the `(index, name)` pairs, at most `concurrency`
+/// at a time; hosts whose detection fails get every fixed check.
+fn detect_all(
+    names: &[(usize, String)],
+    settings: &DetectionSettings,
+    concurrency: usize,
+) -> Vec<(usize, HostConfig)> {
+    let next = AtomicUsize::new(0);
+    let detected = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, names.len().max(1)) {
+            scope.spawn(|| {
+                while let Some((index, name)) = names.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let host = match discovery::detect(name, settings) {
+                        Ok(ports) => HostConfig::with_ports(name.clone(), &ports),
+                        Err(err) => {
+                            warn!("failed to detect services of '{name}': {err}");
+                            HostConfig::with_all_enable(name.clone())
+                        }
+                    };
+                    detected.lock().unwrap().push((*index, host));
+                }
+            });
+        }
+    });
+    detected.into_inner().unwrap()
+}
+
+fn notify(body: &str) {
+    if let Err(err) = Notification::new()
+        .summary("MonitorHosts")
+        .body(body)
+        .show()
+    {
+        error!("failed to show notification '{body}': {err}");
+    }
 }
@@ -0,0 +1,288 @@
+use std::net::{IpAddr, SocketAddr};
+
+use url::Url;
+
+use crate::manager::{HostConfig, Port};
+
+/// Something in the clipboard that can be monitored.
+#[derive(Clone, Debug)]
+pub enum Target {
+    /// A host whose checks follow from how it was written, e.g. a URL or
+    /// `host:port`.
+    Host(HostConfig),
+    /// A bare address or hostname; its services have to be detected.
+    Bare(String),
+    /// A CIDR block or address range to sweep.
+    Range(String),
+}
+
+impl Target {
+    fn name(&self) -> &str {
+        match self {
+            Target::Host(host) => &host.name,
+            Target::Bare(name) | Target::Range(name) => name,
+        }
+    }
+}
+
+/// Finds every URL, `host:port`, address, hostname and CIDR block in `text`,
+/// in order and without repeats.
+pub fn extract(text: &str) -> Vec<Target> {
+    let mut targets: Vec<Target> = Vec::new();
+    // Names are ASCII, so any other character, e.g. CJK text and punctuation
+    // written right next to an address, separates tokens.
+    let tokens = text.split(|c: char| {
+        !c.is_ascii()
+            || c.is_whitespace()
+            || matches!(c, ',' | ';' | '|' | '"' | '\'' | '`' | '<' | '>')
+    });
+    for token in tokens {
+        let token = token.trim_start_matches(['(', '{']);
+        let closed = token.trim_end_matches([')', '}']);
+        let sentence = closed.trim_end_matches([')', '}', '.', '!', '?']);
+        // Sentence punctuation, unless it is part of the address as in `::`.
+        let target = parse(closed)
+            .or_else(|| parse(sentence))
+            .or_else(|| parse(sentence.trim_end_matches(':')));
+        if let Some(target) = target {
+            if !targets.iter().any(|t| t.name() == target.name()) {
+                targets.push(target);
+            }
+        }
+    }
+    targets
+}
+
+fn parse(token: &str) -> Option<Target> {
+    if token.is_empty() {
+        return None;
+    }
+    if token.contains("://") {
+        return url(token);
+    }
+    if token.parse::<IpAddr>().is_ok() {
+        return Some(Target::Bare(token.to_owned()));
+    }
+    if let Some((addr, prefix)) = token.split_once('/') {
+        let valid = addr.parse::<IpAddr>().is_ok() && prefix.parse::<u8>().is_ok();
+        return valid.then(|| Target::Range(token.to_owned()));
+    }
+    if let Some((start, end)) = token.split_once('-') {
+        if let Ok(start) = start.parse::<IpAddr>() {
+            let valid =
+                end.parse::<IpAddr>().is_ok() || (start.is_ipv4() && end.parse::<u8>().is_ok());
+            return valid.then(|| Target::Range(token.to_owned()));
+        }
+    }
+    if let Ok(addr) = token.parse::<SocketAddr>() {
+        let host = HostConfig::with_ports(addr.ip().to_string(), &[addr.port()]);
+        return Some(Target::Host(host));
+    }
+    if let Some((name, port)) = token.rsplit_once(':') {
+        let port: u16 = port.parse().ok()?;
+        return hostname(name)
+            .then(|| Target::Host(HostConfig::with_ports(name.to_owned(), &[port])));
+    }
+    hostname(token).then(|| Target::Bare(token.to_owned()))
+}
+
+/// A URL enables the check matching its scheme, on its port when that is not
+/// the usual one; unknown schemes become a TCP service on their port.
+fn url(token: &str) -> Option<Target> {
+    let url = Url::parse(token).ok()?;
+    let name = url.host_str()?.trim_matches(['[', ']']).to_owned();
+    let fixed = match url.scheme() {
+        "http" | "ws" => Some(Port::Http),
+        "https" | "wss" => Some(Port::Https),
+        "ssh" | "sftp" => Some(Port::Ssh),
+        "vnc" => Some(Port::Vnc),
+        "ipmi" => Some(Port::Ipmi),
+        _ => None,
+    };
+    let host = match (fixed, url.port_or_known_default()) {
+        (Some(port), number) => {
+            let mut host = HostConfig::with_ports(name, &[]);
+            host.ports.insert(port, true);
+            if let Some(number) = number.filter(|n| *n != port.u16()) {
+                host.port_numbers.insert(port, number);
+            }
+            host
+        }
+        (None, Some(number)) => HostConfig::with_ports(name, &[number]),
+        (None, None) => return Some(Target::Bare(name)),
+    };
+    Some(Target::Host(host))
+}
+
+/// Top level domains accepted besides the two letter country codes, which
+/// include internal names commonly used on LANs.
+const TLDS: [&str; 33] = [
+    "com",
+    "net",
+    "org",
+    "edu",
+    "gov",
+    "mil",
+    "int",
+    "info",
+    "biz",
+    "name",
+    "pro",
+    "arpa",
+    "dev",
+    "app",
+    "cloud",
+    "tech",
+    "online",
+    "site",
+    "xyz",
+    "top",
+    "asia",
+    "local",
+    "lan",
+    "internal",
+    "intranet",
+    "corp",
+    "home",
+    "localdomain",
+    "test",
+    "example",
+    "invalid",
+    "lab",
+    "private",
+];
+
+/// Country codes that are far more often file extensions, e.g. `build.rs`.
+const FILE_EXTENSIONS: [&str; 13] = [
+    "ai", "cs", "gz", "js", "md", "mk", "ml", "pl", "ps", "py", "rb", "rs", "sh",
+];
+
+/// Looks like a DNS name: dotted labels of letters, digits and hyphens,
+/// ending in a known top level domain, so that file names are left alone.
+fn hostname(name: &str) -> bool {
+    let labels: Vec<&str> = name.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && labels.last().is_some_and(|tld| known_tld(tld))
+}
+
+fn known_tld(tld: &str) -> bool {
+    let tld = tld.to_ascii_lowercase();
+    if tld.len() == 2 {
+        tld.chars().all(|c| c.is_ascii_alphabetic()) && !FILE_EXTENSIONS.contains(&tld.as_str())
+    } else {
+        TLDS.contains(&tld.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Name and enabled fixed checks with their port, then TCP services.
+    fn describe(target: &Target) -> String {
+        match target {
+            Target::Host(host) => {
+                let mut checks: Vec<String> = host
+                    .ports
+                    .iter()
+                    .filter(|(_, enabled)| **enabled)
+                    .map(|(port, _)| format!("{port}:{}", host.port_number(port)))
+                    .collect();
+                checks.extend(host.tcp_ports().iter().map(|port| format!("TCP:{port}")));
+                format!("host {} {}", host.name, checks.join(" "))
+            }
+            Target::Bare(name) => format!("bare {name}"),
+            Target::Range(range) => format!("range {range}"),
+        }
+    }
+
+    fn found(text: &str) -> Vec<String> {
+        extract(text).iter().map(describe).collect()
+    }
+
+    #[test]
+    fn bare_addresses() {
+        assert_eq!(
+            found("10.0.0.1 fe80::1 ::1 db1.lab.internal"),
+            vec![
+                "bare 10.0.0.1",
+                "bare fe80::1",
+                "bare ::1",
+                "bare db1.lab.internal"
+            ]
+        );
+    }
+
+    #[test]
+    fn host_and_port() {
+        assert_eq!(
+            found("10.0.0.1:22 db.example.com:3306 [fe80::1]:5900"),
+            vec![
+                "host 10.0.0.1 SSH:22",
+                "host db.example.com TCP:3306",
+                "host fe80::1 VNC:5900"
+            ]
+        );
+    }
+
+    #[test]
+    fn urls_keep_non_default_ports() {
+        assert_eq!(
+            found("https://10.0.0.5:8443/login http://web.example.com/ tcp://db.lab:5432 ssh://[fe80::2]:2222"),
+            vec![
+                "host 10.0.0.5 HTTPS:8443",
+                "host web.example.com HTTP:80",
+                "host db.lab TCP:5432",
+                "host fe80::2 SSH:2222"
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(
+            found("10.0.0.0/24 10.0.1.10-20 fe80::1-fe80::9"),
+            vec![
+                "range 10.0.0.0/24",
+                "range 10.0.1.10-20",
+                "range fe80::1-fe80::9"
+            ]
+        );
+        assert!(found("10.0.0.1/abc 10.0.0.1-300").is_empty());
+    }
+
+    #[test]
+    fn trailing_punctuation_is_dropped() {
+        assert_eq!(
+            found("ping 10.0.0.1. then (10.0.0.2), fe80::1: and web.example.com!"),
+            vec![
+                "bare 10.0.0.1",
+                "bare 10.0.0.2",
+                "bare fe80::1",
+                "bare web.example.com"
+            ]
+        );
+        assert_eq!(
+            found("主机10.0.0.3，以及（10.0.0.4）。还有fe80::。"),
+            vec!["bare 10.0.0.3", "bare 10.0.0.4", "bare fe80::"]
+        );
+    }
+
+    #[test]
+    fn file_names_and_words_are_not_hosts() {
+        assert!(found("see build.rs, README.md and config.json or e.g. v1.2").is_empty());
+        assert!(found("localhost 12:30 a-b").is_empty());
+    }
+
+    #[test]
+    fn repeats_are_dropped() {
+        assert_eq!(found("10.0.0.1 10.0.0.1, 10.0.0.1."), vec!["bare 10.0.0.1"]);
+    }
+}
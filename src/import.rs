@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::manager::{HostConfig, Port};
+
+mod csv;
+mod nmap;
+mod ssh;
+mod text;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    /// `address,label,tags,ports` records, with an optional header
+    Csv,
+    /// One address per line
+    Text,
+    /// `/etc/hosts` style `address name [aliases...]` lines
+    Hosts,
+    /// OpenSSH client config such as `~/.ssh/config`
+    Ssh,
+    /// nmap scan results written with `-oX`
+    Nmap,
+}
+
+impl Format {
+    /// Guesses the format from the file name.
+    pub fn detect(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".csv") {
+            Format::Csv
+        } else if name.ends_with(".xml") {
+            Format::Nmap
+        } else if name == "hosts" || name.ends_with(".hosts") {
+            Format::Hosts
+        } else if name.starts_with("ssh_config") || (name == "config" && in_ssh_dir(path)) {
+            Format::Ssh
+        } else {
+            Format::Text
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Duplicate {
+    /// The address is already monitored.
+    Existing,
+    /// The address appears earlier in the same file.
+    Repeated,
+}
+
+/// A host read from a file, as shown in the preview before it is added.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub host: HostConfig,
+    pub duplicate: Option<Duplicate>,
+}
+
+impl Candidate {
+    /// Human readable summary of what would be monitored.
+    pub fn describe(&self) -> String {
+        let mut checks: Vec<String> = self
+            .host
+            .ports
+            .iter()
+            .filter(|(_, enabled)| **enabled)
+            .map(|(port, _)| match self.host.port_numbers.get(port) {
+                Some(number) => format!("{port}:{number}"),
+                None => port.to_string(),
+            })
+            .collect();
+        checks.extend(self.host.services.iter().map(|s| s.name.clone()));
+        let mut parts = vec![checks.join(" ")];
+        if !self.host.tags.is_empty() {
+            parts.push(format!("#{}", self.host.tags.join(" #")));
+        }
+        parts.join("  ")
+    }
+}
+
+/// Reads hosts from `path`, guessing the format when none is given.
+pub fn read(path: &Path, format: Option<Format>) -> Result<Vec<HostConfig>, String> {
+    let content = || std::fs::read_to_string(path).map_err(|err| err.to_string());
+    match format.unwrap_or_else(|| Format::detect(path)) {
+        Format::Csv => csv::parse(&content()?),
+        Format::Text => Ok(text::parse_list(&content()?)),
+        Format::Hosts => Ok(text::parse_hosts(&content()?)),
+        Format::Ssh => ssh::parse(path),
+        Format::Nmap => nmap::parse(&content()?),
+    }
+}
+
+/// The user's OpenSSH client config, if there is one.
+pub fn ssh_config() -> Option<PathBuf> {
+    let path = dirs::home_dir()?.join(".ssh").join("config");
+    path.exists().then_some(path)
+}
+
+fn in_ssh_dir(path: &Path) -> bool {
+    path.parent()
+        .and_then(Path::file_name)
+        .is_some_and(|dir| dir == ".ssh")
+}
+
+/// Flags hosts that are already in `existing` or repeated within `hosts`.
+pub fn preview(hosts: Vec<HostConfig>, existing: &[HostConfig]) -> Vec<Candidate> {
+    let mut seen: HashSet<String> = HashSet::new();
+    hosts
+        .into_iter()
+        .map(|host| {
+            let duplicate = if existing.iter().any(|e| e.name == host.name) {
+                Some(Duplicate::Existing)
+            } else if !seen.insert(host.name.clone()) {
+                Some(Duplicate::Repeated)
+            } else {
+                None
+            };
+            Candidate { host, duplicate }
+        })
+        .collect()
+}
+
+/// Splits a list field such as `web;prod` or `http ssh 8080`.
+fn split_list(field: &str) -> impl Iterator<Item = &str> {
+    field
+        .split(|c: char| c == ';' || c == ',' || c == '|' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+}
+
+/// Port numbers for a list of numbers only, where a name would be ambiguous.
+pub fn parse_port_numbers(field: &str) -> Result<Vec<u16>, String> {
+    split_list(field)
+        .map(|item| {
+            item.parse()
+                .map_err(|_| format!("'{item}' is not a port number"))
+        })
+        .collect()
+}
+
+/// Port numbers for a list of names (`ssh`) and numbers (`8080`).
+pub fn parse_ports(field: &str) -> Result<Vec<u16>, String> {
+    split_list(field)
+        .map(|item| {
+            Port::ALL
+                .into_iter()
+                .find(|port| port.to_string().eq_ignore_ascii_case(item))
+                .map(|port| Ok(port.u16()))
+                .unwrap_or_else(|| item.parse().map_err(|_| format!("unknown port '{item}'")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn port_lists_accept_names_or_numbers() {
+        assert_eq!(parse_ports("ssh; 8080 HTTPS").unwrap(), vec![22, 8080, 443]);
+        assert!(parse_ports("ssh, telnet").is_err());
+        assert_eq!(parse_port_numbers("3306, 8080").unwrap(), vec![3306, 8080]);
+        assert_eq!(parse_port_numbers(" ").unwrap(), Vec::<u16>::new());
+        assert!(parse_port_numbers("3306, ssh").is_err());
+        assert!(parse_port_numbers("70000").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::manager::HostConfig;
+
+use super::{parse_ports, split_list};
+
+/// Parses `address,label,tags,ports` records. Only the address is required;
+/// hosts without a ports column get all fixed checks enabled.
+pub fn parse(content: &str) -> Result<Vec<HostConfig>, String> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .comment(Some(b'#'))
+        .trim(::csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let mut hosts = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|err| err.to_string())?;
+        let field = |n: usize| record.get(n).unwrap_or_default();
+        let address = field(0);
+        if address.is_empty() || (index == 0 && address.eq_ignore_ascii_case("address")) {
+            continue;
+        }
+        let line = record.position().map_or(index as u64 + 1, |p| p.line());
+        let mut host = match field(3) {
+            "" => HostConfig::with_all_enable(address.to_owned()),
+            ports => {
+                let ports = parse_ports(ports).map_err(|err| format!("line {line}: {err}"))?;
+                HostConfig::with_ports(address.to_owned(), &ports)
+            }
+        };
+        if !field(1).is_empty() {
+            host.label = Some(field(1).to_owned());
+        }
+        host.tags = split_list(field(2)).map(str::to_owned).collect();
+        hosts.push(host);
+    }
+    Ok(hosts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manager::Port;
+
+    #[test]
+    fn header_comments_and_blank_lines_are_skipped() {
+        let hosts = parse(
+            "address,label,tags,ports\n\
+             # lab machines\n\
+             \n\
+             10.0.0.1, web, prod;edge, ssh 8080\n\
+             10.0.0.2\n",
+        )
+        .unwrap();
+        let names: Vec<&str> = hosts.iter().map(|host| host.name.as_str()).collect();
+        assert_eq!(names, ["10.0.0.1", "10.0.0.2"]);
+
+        let web = &hosts[0];
+        assert_eq!(web.label.as_deref(), Some("web"));
+        assert_eq!(web.tags, ["prod", "edge"]);
+        assert!(web.ports[&Port::Ssh]);
+        assert!(!web.ports[&Port::Http]);
+        assert_eq!(web.tcp_ports(), [8080]);
+
+        let bare = &hosts[1];
+        assert_eq!(bare.label, None);
+        assert!(bare.ports.values().all(|enabled| *enabled));
+    }
+
+    #[test]
+    fn an_address_column_later_on_is_a_host() {
+        let hosts = parse("10.0.0.1\naddress\n").unwrap();
+        assert_eq!(hosts.len(), 2);
+    }
+
+    #[test]
+    fn bad_ports_report_their_line() {
+        let err = parse("address,label,tags,ports\n# comment\n10.0.0.1,,,ssh\n10.0.0.2,,,telnet\n")
+            .unwrap_err();
+        assert_eq!(err, "line 4: unknown port 'telnet'");
+    }
+}
@@ -0,0 +1,182 @@
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::manager::{HostConfig, Port};
+use crate::service::Service;
+
+/// Turns each `<host>` that nmap saw up into a host with a check for every
+/// open port. Well known ports map to the fixed checks, others become TCP or
+/// UDP services named after what nmap identified.
+pub fn parse(content: &str) -> Result<Vec<HostConfig>, String> {
+    // nmap writes a `<!DOCTYPE nmaprun>`, which is refused by default.
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let document = Document::parse_with_options(content, options).map_err(|err| err.to_string())?;
+    let root = document.root_element();
+    if !root.has_tag_name("nmaprun") {
+        return Err(format!(
+            "expected <nmaprun>, found <{}>",
+            root.tag_name().name()
+        ));
+    }
+    Ok(children(root, "host").filter_map(host).collect())
+}
+
+fn host(node: Node) -> Option<HostConfig> {
+    let up = children(node, "status").all(|status| status.attribute("state") != Some("down"));
+    if !up {
+        return None;
+    }
+    let address = children(node, "address")
+        .filter(|address| matches!(address.attribute("addrtype"), Some("ipv4" | "ipv6")))
+        .find_map(|address| address.attribute("addr"))?;
+    let hostnames: Vec<Node> = children(node, "hostnames")
+        .flat_map(|hostnames| children(hostnames, "hostname"))
+        .collect();
+    // Names given on the command line are more meaningful than reverse DNS.
+    let label = hostnames
+        .iter()
+        .find(|hostname| hostname.attribute("type") == Some("user"))
+        .or_else(|| hostnames.first())
+        .and_then(|hostname| hostname.attribute("name"));
+
+    let mut host = HostConfig::with_ports(address.to_owned(), &[]);
+    host.label = label.map(str::to_owned);
+    for port in children(node, "ports").flat_map(|ports| children(ports, "port")) {
+        let open = children(port, "state").any(|state| state.attribute("state") == Some("open"));
+        let Some(number) = port.attribute("portid").and_then(|id| id.parse().ok()) else {
+            continue;
+        };
+        if !open {
+            continue;
+        }
+        let udp = port.attribute("protocol") == Some("udp");
+        match Port::from_u16(number) {
+            // The fixed checks connect over TCP.
+            Some(fixed) if !udp => {
+                host.ports.insert(fixed, true);
+            }
+            _ => {
+                let mut service = match udp {
+                    // BMCs answer RMCP pings, so a reply can be expected.
+                    true if number == Port::Ipmi.u16() => Service::rmcp(number),
+                    true => Service::udp(number),
+                    false => Service::tcp(number),
+                };
+                if let Some(name) = children(port, "service").find_map(|s| s.attribute("name")) {
+                    // Status is kept per service name, so TCP and UDP on the
+                    // same port must not share one.
+                    service.name = match udp {
+                        true => format!("{name} {number}/udp"),
+                        false => format!("{name} {number}"),
+                    };
+                }
+                let known = host
+                    .services
+                    .iter()
+                    .any(|s| s.port == number && s.is_udp() == udp);
+                if !known {
+                    host.services.push(service);
+                }
+            }
+        }
+    }
+    Some(host)
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::{Payload, ServiceKind};
+
+    const SCAN: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE nmaprun>
+<nmaprun scanner="nmap" args="nmap -sS -sU bmc1 10.0.0.2">
+  <host>
+    <status state="up" reason="echo-reply"/>
+    <address addr="10.0.0.1" addrtype="ipv4"/>
+    <address addr="00:11:22:33:44:55" addrtype="mac"/>
+    <hostnames>
+      <hostname name="bmc1.ptr.example" type="PTR"/>
+      <hostname name="bmc1" type="user"/>
+    </hostnames>
+    <ports>
+      <port protocol="tcp" portid="22"><state state="open"/><service name="ssh"/></port>
+      <port protocol="tcp" portid="443"><state state="closed"/></port>
+      <port protocol="tcp" portid="8080"><state state="open"/><service name="http-proxy"/></port>
+      <port protocol="tcp" portid="53"><state state="open"/><service name="domain"/></port>
+      <port protocol="udp" portid="53"><state state="open"/><service name="domain"/></port>
+      <port protocol="udp" portid="623"><state state="open"/><service name="asf-rmcp"/></port>
+      <port protocol="udp" portid="161"><state state="open|filtered"/></port>
+    </ports>
+  </host>
+  <host>
+    <status state="down" reason="no-response"/>
+    <address addr="10.0.0.2" addrtype="ipv4"/>
+  </host>
+</nmaprun>
+"#;
+
+    #[test]
+    fn open_ports_become_checks() {
+        let hosts = parse(SCAN).unwrap();
+        assert_eq!(hosts.len(), 1);
+        let host = &hosts[0];
+        assert_eq!(host.name, "10.0.0.1");
+        assert_eq!(host.label.as_deref(), Some("bmc1"));
+        let enabled: Vec<Port> = host
+            .ports
+            .iter()
+            .filter(|(_, enabled)| **enabled)
+            .map(|(port, _)| *port)
+            .collect();
+        assert_eq!(enabled, vec![Port::Ssh]);
+
+        let services: Vec<(&str, u16, bool)> = host
+            .services
+            .iter()
+            .map(|s| (s.name.as_str(), s.port, s.is_udp()))
+            .collect();
+        assert_eq!(
+            services,
+            vec![
+                ("http-proxy 8080", 8080, false),
+                ("domain 53", 53, false),
+                ("domain 53/udp", 53, true),
+                ("asf-rmcp 623/udp", 623, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn udp_ipmi_expects_a_presence_pong() {
+        let host = parse(SCAN).unwrap().remove(0);
+        let rmcp = host.services.iter().find(|s| s.port == 623).unwrap();
+        let ServiceKind::Udp { payload, expect } = &rmcp.kind else {
+            panic!("RMCP is checked over UDP");
+        };
+        assert_eq!(payload.bytes().unwrap()[..4], [0x06, 0x00, 0xff, 0x06]);
+        let pong = Payload::Hex("06 00 ff 06 00 00 11 be 40 00 00 10".to_owned());
+        assert!(expect
+            .as_ref()
+            .unwrap()
+            .matches(&pong.bytes().unwrap())
+            .unwrap());
+        assert!(!host.ports[&Port::Ipmi]);
+    }
+
+    #[test]
+    fn other_documents_are_rejected() {
+        assert!(parse("<hosts/>").is_err());
+        assert!(parse("not xml").is_err());
+    }
+}
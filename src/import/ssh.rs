@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::manager::{HostConfig, Port};
+
+/// Nested `Include`s deeper than this are ignored, as OpenSSH does.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A `Host` block and the options it sets that matter here.
+#[derive(Default)]
+struct Block {
+    patterns: Vec<String>,
+    hostname: Option<String>,
+    port: Option<u16>,
+}
+
+/// Reads an OpenSSH client config, following `Include`s, and returns a host
+/// per concrete alias with the SSH check on its configured port.
+///
+/// As in OpenSSH the first value found for an option wins, so `Host *`
+/// defaults at the end only fill in what the alias itself does not set.
+pub fn parse(path: &Path) -> Result<Vec<HostConfig>, String> {
+    let mut blocks = vec![Block {
+        patterns: vec!["*".to_owned()],
+        ..Default::default()
+    }];
+    read(path, &mut blocks, 0)?;
+
+    let mut aliases: Vec<&str> = Vec::new();
+    for block in &blocks {
+        for pattern in &block.patterns {
+            let concrete = !pattern.contains(['*', '?', '!']);
+            if concrete && !aliases.contains(&pattern.as_str()) {
+                aliases.push(pattern);
+            }
+        }
+    }
+
+    Ok(aliases
+        .into_iter()
+        .map(|alias| {
+            let matching: Vec<&Block> = blocks.iter().filter(|b| b.matches(alias)).collect();
+            let hostname = matching
+                .iter()
+                .find_map(|b| b.hostname.as_deref())
+                .map(|hostname| hostname.replace("%h", alias))
+                .unwrap_or_else(|| alias.to_owned());
+            let port = matching.iter().find_map(|b| b.port).unwrap_or(22);
+            let mut host = HostConfig::with_ports(hostname, &[]);
+            host.ports.insert(Port::Ssh, true);
+            if port != Port::Ssh.u16() {
+                host.port_numbers.insert(Port::Ssh, port);
+            }
+            if host.name != alias {
+                host.label = Some(alias.to_owned());
+            }
+            host
+        })
+        .collect())
+}
+
+impl Block {
+    fn matches(&self, alias: &str) -> bool {
+        let mut matched = false;
+        for pattern in &self.patterns {
+            match pattern.strip_prefix('!') {
+                Some(negated) if wildcard(negated, alias) => return false,
+                Some(_) => {}
+                None => matched |= wildcard(pattern, alias),
+            }
+        }
+        matched
+    }
+}
+
+fn read(path: &Path, blocks: &mut Vec<Block>, depth: usize) -> Result<(), String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    for line in content.lines() {
+        let Some((keyword, args)) = split(line) else {
+            continue;
+        };
+        match keyword.to_lowercase().as_str() {
+            "host" => blocks.push(Block {
+                patterns: args,
+                ..Default::default()
+            }),
+            // Conditions cannot be evaluated here, so nothing below applies.
+            "match" => blocks.push(Block::default()),
+            "hostname" => {
+                let block = blocks.last_mut().unwrap();
+                if block.hostname.is_none() {
+                    block.hostname = args.into_iter().next();
+                }
+            }
+            "port" => {
+                let block = blocks.last_mut().unwrap();
+                if block.port.is_none() {
+                    block.port = args.first().and_then(|port| port.parse().ok());
+                }
+            }
+            "include" if depth >= MAX_INCLUDE_DEPTH => {
+                warn!("too many nested includes in '{path:?}'");
+            }
+            "include" => {
+                for pattern in args {
+                    for file in expand(&pattern) {
+                        if let Err(err) = read(&file, blocks, depth + 1) {
+                            warn!("skipping include: {err}");
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Splits `Keyword value...` or `Keyword=value`, honouring double quotes.
+fn split(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let (keyword, rest) = line.split_at(end);
+    let rest = rest.trim_start().trim_start_matches('=');
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    Some((keyword.to_owned(), args))
+}
+
+/// Files named by an `Include` argument: `~` is expanded, relative paths are
+/// taken from `~/.ssh`, and wildcards are allowed in the file name.
+fn expand(pattern: &str) -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let path = match pattern.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None if Path::new(pattern).is_absolute() => PathBuf::from(pattern),
+        None => home.join(".ssh").join(pattern),
+    };
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| wildcard(&name, &entry.file_name().to_string_lossy()))
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Matches `*` and `?` wildcards, case-insensitively as OpenSSH does for
+/// host patterns.
+fn wildcard(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_handles_equals_and_quotes() {
+        let args = |line| split(line).map(|(_, args)| args);
+        assert_eq!(args("  Port=2222"), Some(vec!["2222".to_owned()]));
+        assert_eq!(args("HostName = db.lab"), Some(vec!["db.lab".to_owned()]));
+        assert_eq!(
+            args(r#"Host a "b c""#),
+            Some(vec!["a".to_owned(), "b c".to_owned()])
+        );
+        assert_eq!(split("# Host a"), None);
+        assert_eq!(split(""), None);
+    }
+
+    #[test]
+    fn wildcard_matches_like_openssh() {
+        assert!(wildcard("*.lab", "db.LAB"));
+        assert!(wildcard("web?", "web1"));
+        assert!(!wildcard("web?", "web10"));
+        assert!(wildcard("*", ""));
+        assert!(wildcard("a*b*c", "axxbyyc"));
+        assert!(!wildcard("a*b*c", "axxbyy"));
+    }
+
+    #[test]
+    fn aliases_take_the_first_value_of_each_option() {
+        let dir = std::env::temp_dir().join(format!("monitorhosts-ssh-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let extra = dir.join("extra.conf");
+        std::fs::write(&extra, "Host web\n    HostName 10.0.0.9\n").unwrap();
+        let config = dir.join("config");
+        std::fs::write(
+            &config,
+            format!(
+                "Host jump\n\
+                 \tHostName 10.0.0.1\n\
+                 \tPort 2222\n\
+                 Host db-* !db-old\n\
+                 \tHostName %h.lab.example\n\
+                 Host db-1 db-old\n\
+                 Host *\n\
+                 \tPort 2200\n\
+                 Match host jump\n\
+                 \tHostName ignored\n\
+                 Include {}\n",
+                extra.display()
+            ),
+        )
+        .unwrap();
+
+        let hosts = parse(&config).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let summary: Vec<(&str, Option<&str>, u16)> = hosts
+            .iter()
+            .map(|host| {
+                (
+                    host.name.as_str(),
+                    host.label.as_deref(),
+                    host.port_number(&Port::Ssh),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("10.0.0.1", Some("jump"), 2222),
+                ("db-1.lab.example", Some("db-1"), 2200),
+                ("db-old", None, 2200),
+                ("10.0.0.9", Some("web"), 2200),
+            ]
+        );
+        assert!(hosts.iter().all(|host| host.ports[&Port::Ssh]));
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        assert!(parse(Path::new("/nonexistent/ssh_config")).is_err());
+    }
+}
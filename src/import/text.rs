@@ -0,0 +1,72 @@
+use std::net::IpAddr;
+
+use crate::manager::HostConfig;
+
+/// One address per line; anything after the first word and `#` comments are
+/// ignored.
+pub fn parse_list(content: &str) -> Vec<HostConfig> {
+    lines(content)
+        .filter_map(|line| line.split([' ', '\t', ',']).next())
+        .map(|address| HostConfig::with_all_enable(address.to_owned()))
+        .collect()
+}
+
+/// `address name [aliases...]` lines; the name becomes the label. Loopback,
+/// unspecified and multicast addresses, as used by blocklists and the default
+/// entries, are skipped.
+pub fn parse_hosts(content: &str) -> Vec<HostConfig> {
+    lines(content)
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let address = words.next()?;
+            let ip: IpAddr = address.parse().ok()?;
+            if ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() {
+                return None;
+            }
+            let mut host = HostConfig::with_all_enable(address.to_owned());
+            host.label = words.next().map(str::to_owned);
+            Some(host)
+        })
+        .collect()
+}
+
+fn lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(hosts: &[HostConfig]) -> Vec<&str> {
+        hosts.iter().map(|host| host.name.as_str()).collect()
+    }
+
+    #[test]
+    fn lists_keep_the_first_word() {
+        let hosts = parse_list("# servers\n\nweb1 frontend\n  10.0.0.2,db # primary\n\tnas\n");
+        assert_eq!(names(&hosts), ["web1", "10.0.0.2", "nas"]);
+    }
+
+    #[test]
+    fn hosts_files_skip_local_and_multicast_entries() {
+        let hosts = parse_hosts(
+            "127.0.0.1 localhost\n\
+             ::1 localhost ip6-localhost\n\
+             ff02::1 ip6-allnodes\n\
+             224.0.0.251 mdns\n\
+             0.0.0.0 ads.example\n\
+             \n\
+             # lab\n\
+             10.0.0.1 router gw # core\n\
+             fd00::2\n\
+             not-an-address name\n",
+        );
+        assert_eq!(names(&hosts), ["10.0.0.1", "fd00::2"]);
+        assert_eq!(hosts[0].label.as_deref(), Some("router"));
+        assert_eq!(hosts[1].label, None);
+    }
+}
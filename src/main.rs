@@ -6,12 +6,25 @@ pub mod ui {
 
 mod app;
 mod args;
+mod autostart;
+mod cli;
+mod config;
 mod controllers {
+    pub mod discovery;
+    pub mod liveness;
     pub mod monitor;
+    pub mod saver;
+    pub mod scheduler;
 }
+mod export;
 mod hotkey;
+mod import;
 mod logging;
+mod maintenance;
 mod manager;
+mod notifier;
+mod service;
+mod settings;
 mod tray;
 
 use clap::Parser;
@@ -20,9 +33,31 @@ use app::Application;
 use args::Args;
 
 fn main() {
+    #[cfg(all(windows, not(debug_assertions)))]
+    attach_console();
     let args = Args::parse();
     logging::setup(args.log_level());
 
-    let app = Application::new();
+    if let Some(command) = &args.command {
+        if let Err(err) = cli::run(&args.config_path(), command) {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let app = Application::new(args.config_path(), args.profile);
     app.run();
 }
+
+/// Release builds use the GUI subsystem and start without a console, so the
+/// output of subcommands, `--help` and logs would go nowhere. Borrow the
+/// console of the shell that started us, if there is one.
+#[cfg(all(windows, not(debug_assertions)))]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::manager::HostConfig;
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Maintenance {
+    pub mode: MaintenanceMode,
+    #[serde(flatten)]
+    pub schedule: Schedule,
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaintenanceMode {
+    /// Keep probing, but do not notify.
+    Silence,
+    /// Stop probing the host altogether.
+    Pause,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(tag = "schedule", rename_all = "lowercase")]
+pub enum Schedule {
+    Until {
+        until: DateTime<Local>,
+    },
+    /// Every listed day from `start` to `end`, possibly past midnight.
+    Weekly {
+        days: Vec<Weekday>,
+        start: NaiveTime,
+        end: NaiveTime,
+    },
+}
+
+impl Maintenance {
+    pub fn new(mode: MaintenanceMode, schedule: Schedule) -> Self {
+        Self { mode, schedule }
+    }
+
+    /// When the maintenance ends, if it is active at `now`.
+    pub fn active_until(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match &self.schedule {
+            Schedule::Until { until } => (now < *until).then_some(*until),
+            Schedule::Weekly { days, start, end } => {
+                let today = now.date_naive();
+                let time = now.time();
+                let overnight = start > end;
+                let started_today =
+                    days.contains(&today.weekday()) && time >= *start && (overnight || time < *end);
+                let started_yesterday =
+                    overnight && days.contains(&today.weekday().pred()) && time < *end;
+                let end_date = if started_today && overnight {
+                    today + Duration::days(1)
+                } else if started_today || started_yesterday {
+                    today
+                } else {
+                    return None;
+                };
+                Local
+                    .from_local_datetime(&end_date.and_time(*end))
+                    .earliest()
+            }
+        }
+    }
+
+    /// One-off maintenance that is over for good.
+    pub fn expired(&self, now: DateTime<Local>) -> bool {
+        match &self.schedule {
+            Schedule::Until { until } => now >= *until,
+            Schedule::Weekly { .. } => false,
+        }
+    }
+}
+
+/// The strongest of `entries` active at `now` and when it ends.
+pub fn active<'a>(
+    entries: impl Iterator<Item = &'a Maintenance>,
+    now: DateTime<Local>,
+) -> Option<(MaintenanceMode, DateTime<Local>)> {
+    entries
+        .filter_map(|maintenance| {
+            maintenance
+                .active_until(now)
+                .map(|until| (maintenance.mode, until))
+        })
+        .max()
+}
+
+/// Hosts a maintenance applies to.
+#[derive(Clone, Debug)]
+pub enum Target {
+    Host(String),
+    Group(String),
+}
+
+impl Target {
+    pub fn matches(&self, host: &HostConfig) -> bool {
+        match self {
+            Target::Host(name) => host.name == *name,
+            Target::Group(group) => host.group.as_ref() == Some(group),
+        }
+    }
+}
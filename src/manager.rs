@@ -1,16 +1,39 @@
 use std::{
     collections::{BTreeMap, HashMap},
     hash::Hash,
-    path::Path,
+    time::{Duration, Instant},
 };
 
-use log::{error, trace, warn};
+use chrono::{DateTime, Local};
+use log::{trace, warn};
 use serde::{Deserialize, Serialize};
 
+use crate::config::{self, Document};
+use crate::maintenance::{self as mnt, Maintenance, MaintenanceMode, Target};
+use crate::service::{Service, ServiceKind};
+use crate::settings::{LivenessSettings, Settings};
+
+mod state;
+
+use state::CheckState;
+pub use state::{Change, CheckStatus, Event};
+
 pub struct Manager {
     pub hosts: Vec<HostConfig>,
-    liveness: HashMap<String, PortStatus>,
-    status: HashMap<(String, Port), PortStatus>,
+    pub groups: Vec<GroupConfig>,
+    pub settings: Settings,
+    ping_stats: HashMap<String, PingStats>,
+    liveness_notice: Option<String>,
+    hotkey_notice: Option<String>,
+    status: HashMap<(String, Check), CheckState>,
+    /// Liveness results recorded per host, to tell when a parent was pinged.
+    pings: HashMap<String, u64>,
+    events: Vec<Pending>,
+    /// Hosts under maintenance when last checked, to notice windows starting
+    /// and ending by time.
+    maintained: HashMap<String, MaintenanceMode>,
+    paused: bool,
+    revision: u64,
     updated: bool,
 }
 
@@ -18,124 +41,566 @@ impl Manager {
     pub fn new() -> Self {
         Self {
             hosts: Vec::new(),
-            liveness: HashMap::new(),
+            groups: Vec::new(),
+            settings: Settings::default(),
+            ping_stats: HashMap::new(),
+            liveness_notice: None,
+            hotkey_notice: None,
             status: HashMap::new(),
+            pings: HashMap::new(),
+            events: Vec::new(),
+            maintained: HashMap::new(),
+            paused: false,
+            revision: 0,
             updated: false,
         }
     }
 
-    pub fn with_config(config: &Path) -> Option<Self> {
-        if !config.exists() {
-            warn!("config file '{config:?}' not exists");
-            return None;
-        }
-        let content = match std::fs::read_to_string(config) {
-            Ok(content) => content,
-            Err(err) => {
-                error!("failed to read file '{config:?}': {err}");
-                return None;
-            }
-        };
-        let hosts: Vec<HostConfig> = match serde_json::from_str(&content) {
-            Ok(hosts) => hosts,
-            Err(err) => {
-                error!("failed deserialize '{config:?}': {err}");
-                return None;
-            }
-        };
-        Some(Self {
-            hosts,
-            liveness: HashMap::new(),
-            status: HashMap::new(),
+    pub fn with_document(document: Document) -> Self {
+        Self {
+            hosts: document.hosts,
+            groups: document.groups,
+            settings: document.settings,
             updated: true,
-        })
+            ..Self::new()
+        }
+    }
+
+    /// Replaces the persistent part of the manager, keeping the status of
+    /// hosts and checks that are still configured.
+    pub fn reload(&mut self, document: Document) {
+        trace!("reloading manager from document...");
+        self.hosts = document.hosts;
+        self.groups = document.groups;
+        self.settings = document.settings;
+        self.forget_removed();
+        self.updated = true;
+    }
+
+    /// The persistent part of the manager.
+    pub fn document(&self) -> Document {
+        Document {
+            version: config::VERSION,
+            settings: self.settings.clone(),
+            hosts: self.hosts.clone(),
+            groups: self.groups.clone(),
+        }
     }
 
     pub fn updated(&self) -> bool {
         self.updated
     }
 
+    /// Counts changes to the persistent part of the manager.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Records a change to the persistent part of the manager.
+    pub fn touch(&mut self) {
+        self.revision += 1;
+        self.updated = true;
+    }
+
+    /// Replaces the settings; they take effect as each part next reads them.
+    pub fn set_settings(&mut self, settings: Settings) {
+        trace!("updating settings...");
+        self.settings = settings;
+        self.touch();
+    }
+
+    /// Whether probing is paused; the last known state is kept meanwhile.
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        trace!("setting monitoring paused to {paused}...");
+        if self.paused != paused {
+            self.paused = paused;
+            self.updated = true;
+        }
+    }
+
+    /// Pauses or resumes monitoring, returning whether it is now paused.
+    pub fn toggle_paused(&mut self) -> bool {
+        let paused = !self.paused;
+        self.set_paused(paused);
+        paused
+    }
+
     pub fn contains_host(&self, name: &str) -> bool {
         self.hosts.iter().any(|host| host.name == name)
     }
 
+    /// Where the host called `name` is now; rows move when the config is
+    /// reloaded.
+    pub fn host_index(&self, name: &str) -> Option<usize> {
+        self.hosts.iter().position(|host| host.name == name)
+    }
+
     pub fn add_host(&mut self, host: HostConfig) {
         trace!("inserting host {} to manager...", host.name);
         self.hosts.push(host);
-        self.updated = true;
+        self.touch();
     }
 
     pub fn get_host(&self, index: usize) -> Option<&HostConfig> {
         self.hosts.get(index)
     }
 
+    /// Replaces the host at `index`; children of a renamed host follow it.
     pub fn update_host(&mut self, index: usize, host: HostConfig) {
         trace!("updating host from manager...");
-        self.hosts.remove(index);
+        let old = self.hosts.remove(index);
+        if old.name != host.name {
+            self.hosts
+                .iter_mut()
+                .filter(|child| child.parent.as_ref() == Some(&old.name))
+                .for_each(|child| child.parent = Some(host.name.clone()));
+        }
         self.hosts.insert(index, host);
-        self.updated = true;
+        self.forget_removed();
+        self.touch();
     }
 
     pub fn remove_host(&mut self, index: usize) {
         trace!("removing host from manager...");
         self.hosts.remove(index);
-        self.updated = true;
+        self.forget_removed();
+        self.touch();
     }
 
     pub fn clear_host(&mut self) {
         trace!("clearing hosts from manager...");
         self.hosts.clear();
-        self.updated = true;
+        self.forget_removed();
+        self.touch();
     }
 
-    pub fn update(&mut self, name: String, port: Port, status: PortStatus) {
-        self.status
-            .entry((name, port))
-            .and_modify(|value| {
-                if *value != status {
-                    *value = status;
-                    self.updated = true;
-                }
-            })
-            .or_insert_with(|| {
+    pub fn update(&mut self, name: String, check: Check, status: PortStatus) {
+        let key = (name, check);
+        let state = match self.status.get_mut(&key) {
+            Some(state) => state,
+            None => {
+                self.status.insert(key, CheckState::new(status));
                 self.updated = true;
-                status
+                return;
+            }
+        };
+        let changes = state.record(status, &self.settings.flap);
+        let flapping = state.status().flapping;
+        let parent_pings = self.parent_pings(&key.0);
+        for change in changes {
+            self.updated = true;
+            if matches!(change, Change::Stable { .. }) && flapping {
+                continue;
+            }
+            self.events.push(Pending {
+                event: Event {
+                    name: key.0.clone(),
+                    check: key.1.clone(),
+                    change,
+                },
+                parent_pings,
+                at: Instant::now(),
             });
+        }
     }
 
-    pub fn update_liveness(&mut self, name: String, status: PortStatus) {
-        self.liveness
-            .entry(name)
-            .and_modify(|value| {
-                if *value != status {
-                    *value = status;
+    pub fn update_liveness(&mut self, name: String, status: PortStatus, stats: Option<PingStats>) {
+        match stats {
+            Some(stats) => {
+                if self.ping_stats.insert(name.clone(), stats) != Some(stats) {
+                    self.updated = true;
+                }
+            }
+            None => {
+                if self.ping_stats.remove(&name).is_some() {
                     self.updated = true;
                 }
+            }
+        }
+        *self.pings.entry(name.clone()).or_default() += 1;
+        if !self.events.is_empty() {
+            // Held events may be ready now that another host was pinged.
+            self.updated = true;
+        }
+        self.update(name, Check::Ping, status);
+    }
+
+    pub fn set_liveness_notice(&mut self, notice: Option<String>) {
+        if self.liveness_notice != notice {
+            self.liveness_notice = notice;
+            self.updated = true;
+        }
+    }
+
+    pub fn set_hotkey_notice(&mut self, notice: Option<String>) {
+        if self.hotkey_notice != notice {
+            self.hotkey_notice = notice;
+            self.updated = true;
+        }
+    }
+
+    /// Takes the status changes recorded since the last call, leaving out
+    /// those of hosts whose parent is down. Ports and pings are probed
+    /// independently, so the changes of a host with a parent are held until
+    /// the parent has been pinged again and its own status has settled.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        let now = Local::now();
+        let (ready, held): (Vec<Pending>, Vec<Pending>) = std::mem::take(&mut self.events)
+            .into_iter()
+            .partition(|pending| self.settled(pending));
+        self.events = held;
+        ready
+            .into_iter()
+            .map(|pending| pending.event)
+            .filter(|event| {
+                self.down_parent(&event.name).is_none()
+                    && self.maintenance(&event.name, now).is_none()
             })
-            .or_insert_with(|| {
-                self.updated = true;
-                status
-            });
+            .collect()
+    }
+
+    /// Whether the parent of the host of `pending`, if any, is known to be up
+    /// or down by now. Parents that are not pinged, e.g. because they are
+    /// paused, are waited for only a few rounds.
+    fn settled(&self, pending: &Pending) -> bool {
+        let Some(parent) = self.parent(&pending.event.name) else {
+            return true;
+        };
+        let rounds = self.settings.flap.stable_count.max(1) + 1;
+        if pending.at.elapsed() >= self.settings.probe.interval() * rounds {
+            return true;
+        }
+        let pinged = self.pings.get(parent).copied().unwrap_or_default() > pending.parent_pings;
+        let settling = self
+            .status
+            .get(&(parent.to_owned(), Check::Ping))
+            .and_then(CheckState::pending)
+            .is_some_and(|status| status != PortStatus::On);
+        pinged && !settling
+    }
+
+    /// The configured parent of `name`, if it is a known host.
+    fn parent(&self, name: &str) -> Option<&str> {
+        self.hosts
+            .iter()
+            .find(|host| host.name == name)
+            .and_then(|host| host.parent.as_deref())
+            .filter(|parent| self.contains_host(parent))
+    }
+
+    fn parent_pings(&self, name: &str) -> u64 {
+        self.parent(name)
+            .and_then(|parent| self.pings.get(parent))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Hosts to probe, skipping paused ones and ordering parents before
+    /// their children.
+    pub fn probe_targets(&self) -> Vec<HostConfig> {
+        let now = Local::now();
+        let mut hosts: Vec<HostConfig> = self
+            .hosts
+            .iter()
+            .filter(|host| {
+                !matches!(
+                    self.host_maintenance(host, now),
+                    Some((MaintenanceMode::Pause, _))
+                )
+            })
+            .cloned()
+            .collect();
+        hosts.sort_by_key(|host| self.ancestors(&host.name).len());
+        hosts
+    }
+
+    /// Adds a maintenance to a host, or to a group so that it also covers
+    /// hosts added to the group later.
+    pub fn add_maintenance(&mut self, target: &Target, maintenance: Maintenance) {
+        trace!("adding maintenance {maintenance:?} for {target:?}...");
+        let now = Local::now();
+        let entries = match target {
+            Target::Host(name) => match self.hosts.iter_mut().find(|host| host.name == *name) {
+                Some(host) => &mut host.maintenance,
+                None => {
+                    warn!("no host named {name} to add maintenance to");
+                    return;
+                }
+            },
+            Target::Group(name) => {
+                if !self.groups.iter().any(|group| group.name == *name) {
+                    self.groups.push(GroupConfig::new(name.clone()));
+                }
+                let group = self.groups.iter_mut().find(|group| group.name == *name);
+                &mut group.unwrap().maintenance
+            }
+        };
+        entries.retain(|known| !known.expired(now));
+        entries.push(maintenance);
+        self.touch();
+    }
+
+    /// Ends all maintenance of the target, including that of group members.
+    pub fn clear_maintenance(&mut self, target: &Target) {
+        trace!("clearing maintenance for {target:?}...");
+        self.hosts
+            .iter_mut()
+            .filter(|host| target.matches(host))
+            .for_each(|host| host.maintenance.clear());
+        if let Target::Group(name) = target {
+            self.groups
+                .iter_mut()
+                .filter(|group| group.name == *name)
+                .for_each(|group| group.maintenance.clear());
+        }
+        self.touch();
+    }
+
+    /// Marks the manager updated when a maintenance window started or ended
+    /// since the last call, so that the table shows it.
+    pub fn refresh_maintenance(&mut self) {
+        let now = Local::now();
+        let active: HashMap<String, MaintenanceMode> = self
+            .hosts
+            .iter()
+            .filter_map(|host| {
+                self.host_maintenance(host, now)
+                    .map(|(mode, _)| (host.name.clone(), mode))
+            })
+            .collect();
+        if active != self.maintained {
+            trace!("maintenance changed: {active:?}");
+            self.maintained = active;
+            self.updated = true;
+        }
+    }
+
+    /// The active maintenance of host `name` and when it ends.
+    pub fn maintenance(
+        &self,
+        name: &str,
+        now: DateTime<Local>,
+    ) -> Option<(MaintenanceMode, DateTime<Local>)> {
+        self.hosts
+            .iter()
+            .find(|host| host.name == name)
+            .and_then(|host| self.host_maintenance(host, now))
+    }
+
+    /// The strongest maintenance of `host` or its group active at `now`.
+    fn host_maintenance(
+        &self,
+        host: &HostConfig,
+        now: DateTime<Local>,
+    ) -> Option<(MaintenanceMode, DateTime<Local>)> {
+        let group = self
+            .groups
+            .iter()
+            .filter(|group| host.group.as_ref() == Some(&group.name))
+            .flat_map(|group| group.maintenance.iter());
+        mnt::active(host.maintenance.iter().chain(group), now)
     }
 
+    /// The nearest ancestor of `name` that is down, if any.
+    pub fn down_parent(&self, name: &str) -> Option<String> {
+        self.ancestors(name).into_iter().find(|parent| {
+            self.status
+                .get(&(parent.clone(), Check::Ping))
+                .is_some_and(|state| state.status().status == PortStatus::Off)
+        })
+    }
+
+    /// Parent, grandparent, ... of `name`, stopping at unknown hosts and cycles.
+    fn ancestors(&self, name: &str) -> Vec<String> {
+        let mut ancestors: Vec<String> = Vec::new();
+        let mut current = name;
+        while let Some(parent) = self
+            .hosts
+            .iter()
+            .find(|host| host.name == current)
+            .and_then(|host| host.parent.as_deref())
+        {
+            if parent == name || ancestors.iter().any(|known| known == parent) {
+                warn!("host {name} has a cyclic parent chain");
+                break;
+            }
+            ancestors.push(parent.to_owned());
+            current = parent;
+        }
+        ancestors
+    }
+
+    /// Counts hosts by their worst check, leaving out those under maintenance
+    /// and those not probed yet. Hosts behind a down parent are counted as
+    /// unreachable rather than down.
+    pub fn health(&self) -> Health {
+        let now = Local::now();
+        let mut health = Health {
+            paused: self.paused,
+            ..Default::default()
+        };
+        for host in &self.hosts {
+            if self.host_maintenance(host, now).is_some() {
+                continue;
+            }
+            if self.down_parent(&host.name).is_some() {
+                health.unreachable += 1;
+                continue;
+            }
+            let worst = self
+                .status
+                .iter()
+                .filter(|((name, _), _)| *name == host.name)
+                .map(|(_, state)| state.status().status)
+                .max();
+            match worst {
+                Some(PortStatus::On) => health.up += 1,
+                Some(PortStatus::Degraded) => health.degraded += 1,
+                Some(PortStatus::Off | PortStatus::Error) => health.down += 1,
+                None => {}
+            }
+        }
+        health
+    }
+
+    /// Hosts with checks that are down, in the order of the list, leaving out
+    /// those under maintenance and those behind a down parent.
+    pub fn problems(&self) -> Vec<Problem> {
+        let now = Local::now();
+        self.hosts
+            .iter()
+            .filter(|host| self.host_maintenance(host, now).is_none())
+            .filter(|host| self.down_parent(&host.name).is_none())
+            .filter_map(|host| {
+                let mut checks: Vec<Check> = self
+                    .status
+                    .iter()
+                    .filter(|((name, _), state)| {
+                        *name == host.name
+                            && matches!(state.status().status, PortStatus::Off | PortStatus::Error)
+                    })
+                    .map(|((_, check), _)| check.clone())
+                    .collect();
+                checks.sort();
+                (!checks.is_empty()).then(|| Problem {
+                    name: host.name.clone(),
+                    label: host.label.clone(),
+                    checks,
+                })
+            })
+            .collect()
+    }
+
+    /// Takes a snapshot for the UI and marks it as up to date.
     pub fn capture(&mut self) -> Snapshot {
-        let configs = self.hosts.clone();
-        let liveness = self.liveness.clone();
-        let status = self.status.clone();
         self.updated = false;
-        Snapshot::new(configs, liveness, status)
+        self.snapshot()
     }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let configs = self.hosts.clone();
+        let ping_stats = self.ping_stats.clone();
+        let status = self
+            .status
+            .iter()
+            .map(|(key, state)| (key.clone(), state.status()))
+            .collect();
+        let now = Local::now();
+        let maintenance = self
+            .hosts
+            .iter()
+            .filter_map(|host| {
+                self.host_maintenance(host, now)
+                    .map(|active| (host.name.clone(), active))
+            })
+            .collect();
+        let unreachable = self
+            .hosts
+            .iter()
+            .filter_map(|host| {
+                self.down_parent(&host.name)
+                    .map(|parent| (host.name.clone(), parent))
+            })
+            .collect();
+        Snapshot {
+            configs,
+            ping_stats,
+            unreachable,
+            maintenance,
+            paused: self.paused,
+            notices: [&self.liveness_notice, &self.hotkey_notice]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect(),
+            status,
+        }
+    }
+
+    /// Drops the state of hosts and checks that are no longer configured.
+    fn forget_removed(&mut self) {
+        let hosts = &self.hosts;
+        self.status.retain(|(name, check), _| {
+            hosts
+                .iter()
+                .filter(|host| host.name == *name)
+                .any(|host| host.has_check(check))
+        });
+        self.ping_stats
+            .retain(|name, _| hosts.iter().any(|host| host.name == *name));
+        self.pings
+            .retain(|name, _| hosts.iter().any(|host| host.name == *name));
+    }
+}
+
+/// A status change waiting for the parent of its host to be pinged.
+struct Pending {
+    event: Event,
+    /// Pings of the parent when the change was recorded.
+    parent_pings: u64,
+    at: Instant,
 }
 
 #[derive(Clone, Debug, Deserialize, Hash, Serialize)]
 pub struct HostConfig {
     pub name: String,
+    /// Friendlier name shown next to the address, e.g. from an import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     pub ports: BTreeMap<Port, bool>,
+    /// Non-standard port numbers for the fixed checks, e.g. SSH on 2222.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub port_numbers: BTreeMap<Port, u16>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub services: Vec<Service>,
+    /// Host that must be up for this one to be reachable, e.g. a switch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintenance: Vec<Maintenance>,
 }
 
 impl HostConfig {
     pub fn new(name: String, ports: BTreeMap<Port, bool>) -> Self {
-        Self { name, ports }
+        Self {
+            name,
+            label: None,
+            tags: Vec::new(),
+            ports,
+            port_numbers: BTreeMap::new(),
+            services: Vec::new(),
+            parent: None,
+            group: None,
+            maintenance: Vec::new(),
+        }
     }
 
     pub fn with_all_enable(name: String) -> Self {
@@ -147,6 +612,75 @@ impl HostConfig {
         ports.insert(Port::Ipmi, true);
         Self::new(name, ports)
     }
+
+    /// Enables the fixed checks matching `ports` and adds a TCP service for
+    /// every other port.
+    pub fn with_ports(name: String, ports: &[u16]) -> Self {
+        let mut config = Self::new(name, Port::ALL.iter().map(|port| (*port, false)).collect());
+        config.set_tcp_ports(ports);
+        config
+    }
+
+    /// Makes `ports` the TCP services of this host, enabling the fixed check
+    /// instead where there is one. Other kinds of services are kept.
+    pub fn set_tcp_ports(&mut self, ports: &[u16]) {
+        self.services.retain(|service| {
+            !matches!(service.kind, ServiceKind::Tcp) || ports.contains(&service.port)
+        });
+        for number in ports {
+            match Port::from_u16(*number) {
+                Some(port) => {
+                    self.ports.insert(port, true);
+                }
+                None if self
+                    .services
+                    .iter()
+                    .any(|s| !s.is_udp() && s.port == *number) => {}
+                None => self.services.push(Service::tcp(*number)),
+            }
+        }
+    }
+
+    /// Ports of the TCP services, as edited next to the fixed checks.
+    pub fn tcp_ports(&self) -> Vec<u16> {
+        self.services
+            .iter()
+            .filter(|service| matches!(service.kind, ServiceKind::Tcp))
+            .map(|service| service.port)
+            .collect()
+    }
+
+    /// The port number probed for `port` on this host.
+    pub fn port_number(&self, port: &Port) -> u16 {
+        self.port_numbers
+            .get(port)
+            .copied()
+            .unwrap_or_else(|| port.u16())
+    }
+
+    pub fn has_check(&self, check: &Check) -> bool {
+        match check {
+            Check::Ping => true,
+            Check::Port(port) => self.ports.get(port).copied().unwrap_or(false),
+            Check::Service(name) => self.services.iter().any(|service| service.name == *name),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GroupConfig {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintenance: Vec<Maintenance>,
+}
+
+impl GroupConfig {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            maintenance: Vec::new(),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -159,6 +693,12 @@ pub enum Port {
 }
 
 impl Port {
+    pub const ALL: [Port; 5] = [Port::Http, Port::Https, Port::Ssh, Port::Vnc, Port::Ipmi];
+
+    pub fn from_u16(port: u16) -> Option<Port> {
+        Port::ALL.into_iter().find(|p| p.u16() == port)
+    }
+
     pub fn u16(&self) -> u16 {
         match self {
             Port::Http => 80,
@@ -170,9 +710,39 @@ impl Port {
     }
 }
 
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Check {
+    Ping,
+    Port(Port),
+    Service(String),
+}
+
+impl std::fmt::Display for Port {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Port::Http => write!(f, "HTTP"),
+            Port::Https => write!(f, "HTTPS"),
+            Port::Ssh => write!(f, "SSH"),
+            Port::Vnc => write!(f, "VNC"),
+            Port::Ipmi => write!(f, "IPMI"),
+        }
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Check::Ping => write!(f, "Ping"),
+            Check::Port(port) => write!(f, "{port}"),
+            Check::Service(name) => write!(f, "{name}"),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum PortStatus {
     On,
+    Degraded,
     Off,
     Error,
 }
@@ -181,29 +751,334 @@ impl std::fmt::Display for PortStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             PortStatus::On => write!(f, "⬤"),
+            PortStatus::Degraded => write!(f, "◐"),
             PortStatus::Off => write!(f, "◯"),
             PortStatus::Error => write!(f, "✕"),
         }
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PingStats {
+    pub sent: u16,
+    pub received: u16,
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+    /// Mean difference between the round trip times of consecutive replies.
+    pub jitter: Duration,
+}
+
+impl PingStats {
+    pub fn new(sent: u16, rtts: &[Duration]) -> Self {
+        let received = rtts.len() as u16;
+        let min = rtts.iter().min().copied().unwrap_or_default();
+        let max = rtts.iter().max().copied().unwrap_or_default();
+        let avg = match received {
+            0 => Duration::ZERO,
+            n => rtts.iter().sum::<Duration>() / n as u32,
+        };
+        let jitter = match rtts.len() {
+            0 | 1 => Duration::ZERO,
+            n => {
+                rtts.windows(2)
+                    .map(|pair| pair[0].abs_diff(pair[1]))
+                    .sum::<Duration>()
+                    / (n - 1) as u32
+            }
+        };
+        Self {
+            sent,
+            received,
+            min,
+            avg,
+            max,
+            jitter,
+        }
+    }
+
+    pub fn loss(&self) -> f64 {
+        if self.sent == 0 {
+            return 100.0;
+        }
+        100.0 * f64::from(self.sent.saturating_sub(self.received)) / f64::from(self.sent)
+    }
+
+    pub fn status(&self, settings: &LivenessSettings) -> PortStatus {
+        let loss = self.loss();
+        let avg = self.avg.as_secs_f64() * 1000.0;
+        let jitter = self.jitter.as_secs_f64() * 1000.0;
+        if self.received == 0 || loss >= settings.off_loss {
+            PortStatus::Off
+        } else if loss > settings.degraded_loss
+            || settings.degraded_rtt_ms.is_some_and(|limit| avg > limit)
+            || settings
+                .degraded_jitter_ms
+                .is_some_and(|limit| jitter > limit)
+        {
+            PortStatus::Degraded
+        } else {
+            PortStatus::On
+        }
+    }
+}
+
+impl std::fmt::Display for PingStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        if self.received == 0 {
+            return write!(f, "{:.0}%", self.loss());
+        }
+        write!(
+            f,
+            "{:.0}% {:.1}/{:.1}/{:.1}ms ±{:.1}ms",
+            self.loss(),
+            ms(self.min),
+            ms(self.avg),
+            ms(self.max),
+            ms(self.jitter)
+        )
+    }
+}
+
+/// A host with failing checks, for the tray.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    pub name: String,
+    pub label: Option<String>,
+    pub checks: Vec<Check>,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let checks: Vec<String> = self.checks.iter().map(Check::to_string).collect();
+        match &self.label {
+            Some(label) => write!(f, "{label} ({})：{}", self.name, checks.join("、")),
+            None => write!(f, "{}：{}", self.name, checks.join("、")),
+        }
+    }
+}
+
+/// How many hosts are up, degraded or down, for the tray.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Health {
+    pub up: usize,
+    pub degraded: usize,
+    pub down: usize,
+    /// Hosts whose parent is down; their own state is unknown.
+    pub unreachable: usize,
+    pub paused: bool,
+}
+
+impl Health {
+    /// The worst state of all hosts.
+    pub fn status(&self) -> PortStatus {
+        if self.down > 0 {
+            PortStatus::Off
+        } else if self.degraded > 0 {
+            PortStatus::Degraded
+        } else {
+            PortStatus::On
+        }
+    }
+}
+
+impl std::fmt::Display for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "正常 {}", self.up)?;
+        if self.degraded > 0 {
+            write!(f, "，降级 {}", self.degraded)?;
+        }
+        if self.down > 0 {
+            write!(f, "，故障 {}", self.down)?;
+        }
+        if self.unreachable > 0 {
+            write!(f, "，不可达 {}", self.unreachable)?;
+        }
+        if self.paused {
+            write!(f, "（已暂停）")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Snapshot {
     pub configs: Vec<HostConfig>,
-    pub liveness: HashMap<String, PortStatus>,
-    pub status: HashMap<(String, Port), PortStatus>,
+    pub ping_stats: HashMap<String, PingStats>,
+    /// Hosts whose parent is down, with the name of that parent.
+    pub unreachable: HashMap<String, String>,
+    /// Hosts under maintenance, with its kind and end.
+    pub maintenance: HashMap<String, (MaintenanceMode, DateTime<Local>)>,
+    /// Monitoring is paused, so the status shown is stale.
+    pub paused: bool,
+    /// Problems worth showing, e.g. why liveness is not checked with ICMP.
+    pub notices: Vec<String>,
+    pub status: HashMap<(String, Check), CheckStatus>,
 }
 
-impl Snapshot {
-    pub fn new(
-        configs: Vec<HostConfig>,
-        liveness: HashMap<String, PortStatus>,
-        status: HashMap<(String, Port), PortStatus>,
-    ) -> Self {
-        Self {
-            configs,
-            liveness,
-            status,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maintenance::Schedule;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// A server behind a switch, both up.
+    fn behind_switch() -> Manager {
+        let mut server = HostConfig::with_ports("server".to_owned(), &[]);
+        server.parent = Some("switch".to_owned());
+        let mut manager = Manager::new();
+        manager.hosts = vec![HostConfig::with_ports("switch".to_owned(), &[]), server];
+        manager.update_liveness("switch".to_owned(), PortStatus::On, None);
+        manager.update_liveness("server".to_owned(), PortStatus::On, None);
+        manager
+    }
+
+    fn ping(manager: &mut Manager, name: &str, status: PortStatus) {
+        manager.update_liveness(name.to_owned(), status, None);
+    }
+
+    fn names(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|event| event.name.as_str()).collect()
+    }
+
+    #[test]
+    fn child_going_down_before_parent_is_suppressed() {
+        let mut manager = behind_switch();
+        ping(&mut manager, "server", PortStatus::Off);
+        ping(&mut manager, "server", PortStatus::Off);
+        assert!(manager.take_events().is_empty());
+        // The switch is pinged, but is not down for good yet.
+        ping(&mut manager, "switch", PortStatus::Off);
+        assert!(manager.take_events().is_empty());
+        ping(&mut manager, "switch", PortStatus::Off);
+        assert_eq!(names(&manager.take_events()), ["switch"]);
+        assert!(manager.events.is_empty());
+    }
+
+    #[test]
+    fn child_event_is_released_once_parent_is_up() {
+        let mut manager = behind_switch();
+        ping(&mut manager, "server", PortStatus::Off);
+        ping(&mut manager, "server", PortStatus::Off);
+        assert!(manager.take_events().is_empty());
+        ping(&mut manager, "switch", PortStatus::On);
+        assert_eq!(names(&manager.take_events()), ["server"]);
+    }
+
+    #[test]
+    fn hosts_behind_a_down_parent_are_not_problems() {
+        let mut manager = behind_switch();
+        for _ in 0..2 {
+            ping(&mut manager, "switch", PortStatus::Off);
+            ping(&mut manager, "server", PortStatus::Off);
         }
+        let health = manager.health();
+        assert_eq!((health.down, health.unreachable), (1, 1));
+        assert_eq!(health.to_string(), "正常 0，故障 1，不可达 1");
+        let problems = manager.problems();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].name, "switch");
+
+        ping(&mut manager, "switch", PortStatus::On);
+        ping(&mut manager, "switch", PortStatus::On);
+        let health = manager.health();
+        assert_eq!((health.up, health.down, health.unreachable), (1, 1, 0));
+        assert_eq!(manager.problems()[0].name, "server");
+    }
+
+    #[test]
+    fn events_without_parent_are_not_held() {
+        let mut manager = behind_switch();
+        ping(&mut manager, "switch", PortStatus::Off);
+        ping(&mut manager, "switch", PortStatus::Off);
+        assert_eq!(names(&manager.take_events()), ["switch"]);
+    }
+
+    #[test]
+    fn renaming_parent_updates_children() {
+        let mut manager = behind_switch();
+        let mut switch = manager.hosts[0].clone();
+        switch.name = "core-switch".to_owned();
+        manager.update_host(0, switch);
+        assert_eq!(manager.hosts[1].parent.as_deref(), Some("core-switch"));
+    }
+
+    #[test]
+    fn maintenance_ending_by_time_updates() {
+        let mut manager = behind_switch();
+        let until = |hours| Schedule::Until {
+            until: Local::now() + chrono::Duration::hours(hours),
+        };
+        manager.hosts[0].maintenance = vec![Maintenance::new(MaintenanceMode::Silence, until(1))];
+        manager.refresh_maintenance();
+        assert!(manager.updated());
+        manager.capture();
+        manager.refresh_maintenance();
+        assert!(!manager.updated());
+        // The window is over without anyone touching the host.
+        manager.hosts[0].maintenance = vec![Maintenance::new(MaintenanceMode::Silence, until(-1))];
+        manager.refresh_maintenance();
+        assert!(manager.updated());
+    }
+
+    #[test]
+    fn ping_stats_of_replies() {
+        let stats = PingStats::new(4, &[ms(10), ms(30), ms(20)]);
+        assert_eq!(stats.received, 3);
+        assert_eq!(stats.min, ms(10));
+        assert_eq!(stats.avg, ms(20));
+        assert_eq!(stats.max, ms(30));
+        // |10 - 30| and |30 - 20|
+        assert_eq!(stats.jitter, ms(15));
+        assert_eq!(stats.loss(), 25.0);
+    }
+
+    #[test]
+    fn ping_stats_without_replies() {
+        let stats = PingStats::new(5, &[]);
+        assert_eq!(stats.avg, Duration::ZERO);
+        assert_eq!(stats.jitter, Duration::ZERO);
+        assert_eq!(stats.loss(), 100.0);
+        assert_eq!(stats.status(&LivenessSettings::default()), PortStatus::Off);
+        assert_eq!(stats.to_string(), "100%");
+    }
+
+    #[test]
+    fn ping_stats_single_reply_has_no_jitter() {
+        let stats = PingStats::new(1, &[ms(7)]);
+        assert_eq!(stats.jitter, Duration::ZERO);
+        assert_eq!(stats.loss(), 0.0);
+    }
+
+    #[test]
+    fn ping_stats_nothing_sent_is_total_loss() {
+        assert_eq!(PingStats::new(0, &[]).loss(), 100.0);
+        assert_eq!(PingStats::new(0, &[ms(1)]).loss(), 100.0);
+    }
+
+    #[test]
+    fn ping_stats_status_thresholds() {
+        let settings = LivenessSettings {
+            degraded_loss: 10.0,
+            off_loss: 50.0,
+            degraded_rtt_ms: Some(100.0),
+            degraded_jitter_ms: Some(20.0),
+            ..LivenessSettings::default()
+        };
+        let status = |sent, rtts: &[u64]| {
+            let rtts: Vec<Duration> = rtts.iter().map(|rtt| ms(*rtt)).collect();
+            PingStats::new(sent, &rtts).status(&settings)
+        };
+        assert_eq!(status(10, &[5; 10]), PortStatus::On);
+        // 10% loss is not above the limit, 20% is.
+        assert_eq!(status(10, &[5; 9]), PortStatus::On);
+        assert_eq!(status(10, &[5; 8]), PortStatus::Degraded);
+        assert_eq!(status(10, &[5; 5]), PortStatus::Off);
+        assert_eq!(status(2, &[150, 150]), PortStatus::Degraded);
+        assert_eq!(status(3, &[5, 40, 5]), PortStatus::Degraded);
     }
 }
@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+use super::{Check, PortStatus};
+use crate::settings::FlapSettings;
+
+/// Tracks the raw results of a single check and derives a stable status from
+/// them, so that a single odd result does not flip what is shown.
+#[derive(Clone, Debug)]
+pub struct CheckState {
+    stable: PortStatus,
+    since: Option<SystemTime>,
+    pending: Option<(PortStatus, u32)>,
+    history: VecDeque<PortStatus>,
+    flapping: bool,
+}
+
+impl CheckState {
+    pub fn new(status: PortStatus) -> Self {
+        Self {
+            stable: status,
+            since: None,
+            pending: None,
+            history: VecDeque::from([status]),
+            flapping: false,
+        }
+    }
+
+    pub fn record(&mut self, status: PortStatus, settings: &FlapSettings) -> Vec<Change> {
+        let mut changes = Vec::new();
+
+        self.history.push_back(status);
+        let window = settings.window.max(2);
+        while self.history.len() > window {
+            self.history.pop_front();
+        }
+        let rate = self.change_rate();
+        if !self.flapping && self.history.len() == window && rate >= settings.high_rate {
+            self.flapping = true;
+            changes.push(Change::FlapStart);
+        } else if self.flapping && rate <= settings.low_rate {
+            self.flapping = false;
+            changes.push(Change::FlapStop(self.stable));
+        }
+
+        if status == self.stable {
+            self.pending = None;
+            return changes;
+        }
+        let count = match self.pending {
+            Some((pending, count)) if pending == status => count + 1,
+            _ => 1,
+        };
+        if count >= settings.stable_count.max(1) {
+            changes.push(Change::Stable {
+                from: self.stable,
+                to: status,
+            });
+            self.stable = status;
+            self.since = Some(SystemTime::now());
+            self.pending = None;
+        } else {
+            self.pending = Some((status, count));
+        }
+        changes
+    }
+
+    /// A different result seen too few times to become the status yet.
+    pub fn pending(&self) -> Option<PortStatus> {
+        self.pending.map(|(status, _)| status)
+    }
+
+    pub fn status(&self) -> CheckStatus {
+        CheckStatus {
+            status: self.stable,
+            flapping: self.flapping,
+            since: self.since,
+        }
+    }
+
+    /// Fraction of consecutive results in the history that differ.
+    fn change_rate(&self) -> f64 {
+        if self.history.len() < 2 {
+            return 0.0;
+        }
+        let changes = self
+            .history
+            .iter()
+            .zip(self.history.iter().skip(1))
+            .filter(|(a, b)| a != b)
+            .count();
+        changes as f64 / (self.history.len() - 1) as f64
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CheckStatus {
+    pub status: PortStatus,
+    pub flapping: bool,
+    /// When the stable status last changed; `None` while it is still the
+    /// first result seen.
+    pub since: Option<SystemTime>,
+}
+
+impl std::fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.flapping {
+            write!(f, "⇅")
+        } else {
+            write!(f, "{}", self.status)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Change {
+    Stable {
+        from: PortStatus,
+        to: PortStatus,
+    },
+    FlapStart,
+    /// Flapping stopped, settling on the contained status.
+    FlapStop(PortStatus),
+}
+
+#[derive(Clone, Debug)]
+pub struct Event {
+    pub name: String,
+    pub check: Check,
+    pub change: Change,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PortStatus::{Degraded, Off, On};
+
+    fn settings() -> FlapSettings {
+        FlapSettings {
+            window: 10,
+            high_rate: 0.6,
+            low_rate: 0.3,
+            stable_count: 2,
+        }
+    }
+
+    fn flap_settings() -> FlapSettings {
+        FlapSettings {
+            window: 4,
+            ..settings()
+        }
+    }
+
+    #[test]
+    fn single_odd_result_is_ignored() {
+        let mut state = CheckState::new(On);
+        assert!(state.record(Off, &settings()).is_empty());
+        assert!(state.record(On, &settings()).is_empty());
+        assert_eq!(state.status().status, On);
+    }
+
+    #[test]
+    fn repeated_result_becomes_stable() {
+        let mut state = CheckState::new(On);
+        assert!(state.record(Off, &settings()).is_empty());
+        assert!(state.status().since.is_none());
+        assert_eq!(
+            state.record(Off, &settings()),
+            vec![Change::Stable { from: On, to: Off }]
+        );
+        assert_eq!(state.status().status, Off);
+        assert!(state.status().since.is_some());
+        assert!(state.record(Off, &settings()).is_empty());
+    }
+
+    #[test]
+    fn different_pending_result_restarts_count() {
+        let mut state = CheckState::new(On);
+        state.record(Off, &settings());
+        assert!(state.record(Degraded, &settings()).is_empty());
+        assert_eq!(
+            state.record(Degraded, &settings()),
+            vec![Change::Stable {
+                from: On,
+                to: Degraded
+            }]
+        );
+    }
+
+    #[test]
+    fn stable_count_of_one_changes_at_once() {
+        let settings = FlapSettings {
+            stable_count: 1,
+            ..settings()
+        };
+        let mut state = CheckState::new(On);
+        assert_eq!(
+            state.record(Off, &settings),
+            vec![Change::Stable { from: On, to: Off }]
+        );
+    }
+
+    #[test]
+    fn flapping_starts_and_stops() {
+        let settings = flap_settings();
+        let mut state = CheckState::new(On);
+        state.record(Off, &settings);
+        state.record(On, &settings);
+        // The window is full only now: On, Off, On, Off changes every time.
+        assert_eq!(state.record(Off, &settings), vec![Change::FlapStart]);
+        assert!(state.status().flapping);
+        assert_eq!(state.status().to_string(), "⇅");
+
+        state.record(On, &settings);
+        state.record(On, &settings);
+        // Off, On, On, On still changes a third of the time.
+        assert!(state.record(On, &settings).is_empty());
+        assert_eq!(state.record(On, &settings), vec![Change::FlapStop(On)]);
+        assert!(!state.status().flapping);
+    }
+
+    #[test]
+    fn no_flapping_before_window_is_full() {
+        let settings = flap_settings();
+        let mut state = CheckState::new(On);
+        assert!(state.record(Off, &settings).is_empty());
+        assert!(state.record(On, &settings).is_empty());
+        assert!(!state.status().flapping);
+    }
+}
@@ -0,0 +1,56 @@
+use log::error;
+use notify_rust::Notification;
+
+use crate::manager::{Change, Event, PortStatus};
+use crate::settings::NotificationSettings;
+
+/// Shows a single desktop notification summarizing the `events` that
+/// `settings` ask to be notified of.
+pub fn notify(events: &[Event], settings: &NotificationSettings) {
+    let events: Vec<&Event> = events
+        .iter()
+        .filter(|event| wanted(event, settings))
+        .collect();
+    if events.is_empty() {
+        return;
+    }
+    let lines: Vec<String> = events
+        .iter()
+        .map(|event| {
+            let name = &event.name;
+            let check = &event.check;
+            match event.change {
+                Change::Stable { from, to } => format!("{name} {check}：{from} → {to}"),
+                Change::FlapStart => format!("{name} {check}：状态频繁变化"),
+                Change::FlapStop(status) => format!("{name} {check}：状态已稳定 {status}"),
+            }
+        })
+        .collect();
+    if let Err(err) = Notification::new()
+        .summary("MonitorHosts")
+        .body(&lines.join("\n"))
+        .show()
+    {
+        error!(
+            "failed to show notification for {} events: {err}",
+            events.len()
+        );
+    }
+}
+
+fn wanted(event: &Event, settings: &NotificationSettings) -> bool {
+    match event.change {
+        Change::Stable {
+            to: PortStatus::Off | PortStatus::Error,
+            ..
+        } => true,
+        Change::Stable {
+            to: PortStatus::On, ..
+        } => settings.recovery,
+        Change::Stable {
+            to: PortStatus::Degraded,
+            ..
+        } => settings.degraded,
+        Change::FlapStart | Change::FlapStop(_) => settings.flapping,
+    }
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+/// A user defined check that is probed alongside the fixed `Port` checks.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Service {
+    pub name: String,
+    pub port: u16,
+    #[serde(flatten)]
+    pub kind: ServiceKind,
+}
+
+impl Service {
+    /// A plain TCP connect check, named after its port.
+    pub fn tcp(port: u16) -> Self {
+        Self {
+            name: format!("TCP {port}"),
+            port,
+            kind: ServiceKind::Tcp,
+        }
+    }
+
+    /// A UDP check that sends an empty datagram and expects no port
+    /// unreachable in return.
+    pub fn udp(port: u16) -> Self {
+        Self {
+            name: format!("UDP {port}"),
+            port,
+            kind: ServiceKind::Udp {
+                payload: Payload::Text(String::new()),
+                expect: None,
+            },
+        }
+    }
+
+    /// An RMCP presence ping, which IPMI BMCs answer with a pong, usually on
+    /// UDP 623.
+    pub fn rmcp(port: u16) -> Self {
+        Self {
+            name: format!("RMCP {port}"),
+            port,
+            kind: ServiceKind::Udp {
+                payload: Payload::Hex("06 00 ff 06 00 00 11 be 80 00 00 00".to_owned()),
+                // ASF IANA number followed by the presence pong type.
+                expect: Some(Payload::Hex("00 00 11 be 40".to_owned())),
+            },
+        }
+    }
+
+    pub fn is_udp(&self) -> bool {
+        matches!(self.kind, ServiceKind::Udp { .. })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ServiceKind {
+    /// Up when a TCP connection can be established.
+    Tcp,
+    /// Sends `payload` as a single datagram. When `expect` is set the service
+    /// is up only if the response contains it; otherwise any response, or no
+    /// ICMP port unreachable within the timeout, counts as up.
+    Udp {
+        payload: Payload,
+        #[serde(default)]
+        expect: Option<Payload>,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Payload {
+    Text(String),
+    Hex(String),
+}
+
+impl Payload {
+    pub fn bytes(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Payload::Text(text) => Ok(text.as_bytes().to_vec()),
+            Payload::Hex(hex) => {
+                let digits: Vec<u8> = hex
+                    .bytes()
+                    .filter(|b| !b.is_ascii_whitespace() && *b != b':')
+                    .collect();
+                if let Some(b) = digits.iter().find(|b| !b.is_ascii_hexdigit()) {
+                    return Err(format!("invalid hex digit '{}' in '{hex}'", *b as char));
+                }
+                if !digits.len().is_multiple_of(2) {
+                    return Err(format!("odd number of hex digits in '{hex}'"));
+                }
+                Ok(digits
+                    .chunks(2)
+                    .map(|pair| (hex_value(pair[0]) << 4) | hex_value(pair[1]))
+                    .collect())
+            }
+        }
+    }
+
+    pub fn matches(&self, response: &[u8]) -> Result<bool, String> {
+        let pattern = self.bytes()?;
+        if pattern.is_empty() {
+            return Ok(true);
+        }
+        Ok(response
+            .windows(pattern.len())
+            .any(|window| window == pattern.as_slice()))
+    }
+}
+
+fn hex_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_payload_is_sent_as_is() {
+        let payload = Payload::Text("ping\n".to_owned());
+        assert_eq!(payload.bytes().unwrap(), b"ping\n");
+        assert_eq!(Payload::Text(String::new()).bytes().unwrap(), b"");
+    }
+
+    #[test]
+    fn hex_payload_ignores_separators() {
+        let expected = vec![0x06, 0x00, 0xff, 0x07];
+        assert_eq!(
+            Payload::Hex("0600ff07".to_owned()).bytes().unwrap(),
+            expected
+        );
+        assert_eq!(
+            Payload::Hex("06 00 FF 07".to_owned()).bytes().unwrap(),
+            expected
+        );
+        assert_eq!(
+            Payload::Hex("06:00:ff:07".to_owned()).bytes().unwrap(),
+            expected
+        );
+        assert_eq!(
+            Payload::Hex("06\n00\tfF07".to_owned()).bytes().unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn invalid_hex_payload_is_rejected() {
+        assert!(Payload::Hex("abc".to_owned()).bytes().is_err());
+        assert!(Payload::Hex("0g".to_owned()).bytes().is_err());
+        assert!(Payload::Hex("+1".to_owned()).bytes().is_err());
+        assert!(Payload::Hex("é1".to_owned()).bytes().is_err());
+    }
+
+    #[test]
+    fn expected_payload_is_searched_in_the_response() {
+        let expect = Payload::Hex("0102".to_owned());
+        assert!(expect.matches(&[0, 1, 2, 3]).unwrap());
+        assert!(!expect.matches(&[0, 1, 3, 2]).unwrap());
+        assert!(!expect.matches(&[1]).unwrap());
+        assert!(Payload::Text(String::new()).matches(&[]).unwrap());
+        assert!(Payload::Hex("1".to_owned()).matches(&[1]).is_err());
+    }
+}
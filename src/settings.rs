@@ -0,0 +1,252 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    pub probe: ProbeSettings,
+    pub liveness: LivenessSettings,
+    pub flap: FlapSettings,
+    pub notifications: NotificationSettings,
+    pub persistence: PersistenceSettings,
+    pub discovery: DiscoverySettings,
+    pub detection: DetectionSettings,
+    pub hotkeys: HotkeySettings,
+    pub ui: UiSettings,
+    pub startup: StartupSettings,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProbeSettings {
+    /// Time between two rounds of probes.
+    pub interval_secs: u64,
+    /// Timeout of a single port or service check.
+    pub timeout_ms: u64,
+    /// Hosts checked at the same time, both for ports and services and for
+    /// liveness.
+    pub concurrency: usize,
+}
+
+impl ProbeSettings {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.max(1))
+    }
+}
+
+impl Default for ProbeSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            timeout_ms: 1000,
+            concurrency: 8,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LivenessSettings {
+    /// Number of echo requests sent per liveness check.
+    pub count: u16,
+    /// Pause between two echo requests of the same burst.
+    pub interval_ms: u64,
+    pub timeout_ms: u64,
+    /// Loss percentage above which a host is considered degraded.
+    pub degraded_loss: f64,
+    /// Loss percentage at or above which a host is considered down.
+    pub off_loss: f64,
+    pub degraded_rtt_ms: Option<f64>,
+    pub degraded_jitter_ms: Option<f64>,
+}
+
+impl Default for LivenessSettings {
+    fn default() -> Self {
+        Self {
+            count: 5,
+            interval_ms: 200,
+            timeout_ms: 1000,
+            degraded_loss: 0.0,
+            off_loss: 100.0,
+            degraded_rtt_ms: None,
+            degraded_jitter_ms: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FlapSettings {
+    /// Number of recent results considered when computing the change rate.
+    pub window: usize,
+    /// Change rate at or above which a check starts flapping.
+    pub high_rate: f64,
+    /// Change rate at or below which a flapping check becomes stable again.
+    pub low_rate: f64,
+    /// Consecutive identical results required before a new status is shown.
+    pub stable_count: u32,
+}
+
+impl Default for FlapSettings {
+    fn default() -> Self {
+        Self {
+            window: 10,
+            high_rate: 0.5,
+            low_rate: 0.25,
+            stable_count: 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// Also notify when a check comes back up; going down is always notified.
+    pub recovery: bool,
+    /// Also notify when a check becomes degraded.
+    pub degraded: bool,
+    /// Also notify when a check starts or stops flapping.
+    pub flapping: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            recovery: true,
+            degraded: true,
+            flapping: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PersistenceSettings {
+    /// How long to wait after a change before saving, to batch bursts.
+    pub delay_ms: u64,
+    /// Number of previous versions of the config file to keep.
+    pub backups: usize,
+}
+
+impl Default for PersistenceSettings {
+    fn default() -> Self {
+        Self {
+            delay_ms: 1000,
+            backups: 5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiscoverySettings {
+    /// Addresses probed at the same time during a sweep.
+    pub concurrency: usize,
+    /// Upper bound on pings and connection attempts started per second.
+    pub rate: u32,
+    pub timeout_ms: u64,
+    /// TCP ports tried on every address.
+    pub ports: Vec<u16>,
+}
+
+impl Default for DiscoverySettings {
+    fn default() -> Self {
+        Self {
+            concurrency: 64,
+            rate: 200,
+            timeout_ms: 500,
+            ports: vec![22, 80, 443, 623, 5900],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DetectionSettings {
+    /// TCP ports tried when detecting the services of a single host.
+    pub ports: Vec<u16>,
+    pub timeout_ms: u64,
+}
+
+impl Default for DetectionSettings {
+    fn default() -> Self {
+        Self {
+            ports: vec![
+                21, 22, 23, 80, 443, 623, 3306, 3389, 5432, 5900, 8006, 8080, 8443, 9090,
+            ],
+            timeout_ms: 1000,
+        }
+    }
+}
+
+/// Global hotkeys such as `ctrl+alt+shift+KeyM`; an empty or missing value
+/// leaves the action unbound.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct HotkeySettings {
+    /// Adds the hosts found in the clipboard.
+    pub add_host: Option<String>,
+    pub probe_all: Option<String>,
+    /// Shows the main window, or hides it when shown.
+    pub toggle_window: Option<String>,
+    pub toggle_pause: Option<String>,
+    /// Shows the host found by `add_host` for confirmation instead of adding
+    /// it right away.
+    pub confirm_add: bool,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            add_host: Some("ctrl+alt+shift+KeyM".to_owned()),
+            probe_all: Some("ctrl+alt+shift+KeyR".to_owned()),
+            toggle_window: None,
+            toggle_pause: None,
+            confirm_add: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UiSettings {
+    pub theme: Theme,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follows the color scheme of the system.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StartupSettings {
+    /// Opens the main window at startup instead of only the tray icon.
+    pub show_window: bool,
+    /// Starts the monitor when the user logs in.
+    pub autostart: bool,
+}
@@ -1,64 +1,99 @@
-use std::sync::mpsc;
+use std::path::PathBuf;
+use std::sync::{
+    mpsc::{self, RecvTimeoutError},
+    Arc, RwLock,
+};
 use std::thread;
-
-use log::{trace, warn};
-use slint::ComponentHandle;
-use tray_item::{IconSource, TrayItem};
-
-use crate::ui::MainWindow;
+use std::time::Duration;
+
+use log::{error, trace, warn};
+use slint::{CloseRequestResponse, ComponentHandle, Weak};
+
+use crate::app::dialog;
+use crate::controllers::scheduler::{ProbeRequest, Scheduler};
+use crate::{
+    manager::{Health, Manager, PortStatus, Problem},
+    ui::MainWindow,
+};
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod sni;
+#[cfg(not(any(target_os = "windows", unix)))]
+mod unsupported;
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "macos")]
+use macos::Backend;
+#[cfg(all(unix, not(target_os = "macos")))]
+use sni::Backend;
+#[cfg(not(any(target_os = "windows", unix)))]
+use unsupported::Backend;
+#[cfg(target_os = "windows")]
+use windows::Backend;
+
+/// Down hosts listed in the menu; the rest are only counted.
+const MAX_PROBLEMS: usize = 10;
+
+/// Menu entries below the problem hosts, before "退出".
+const ACTIONS: [(&str, Message); 4] = [
+    ("主界面", Message::ShowMainWindow),
+    ("暂停/恢复监控", Message::TogglePause),
+    ("设置", Message::Config),
+    ("关于", Message::About),
+];
 
 pub struct Tray {
     pub thread: std::thread::JoinHandle<()>,
 }
 
 impl Tray {
-    pub fn new(window: &MainWindow) -> Self {
+    pub fn new(
+        window: &MainWindow,
+        title: String,
+        config: PathBuf,
+        manager: Arc<RwLock<Manager>>,
+        scheduler: Arc<Scheduler>,
+    ) -> Self {
         let window_weak = window.as_weak();
 
         let thread = thread::spawn(move || {
-            let mut tray =
-                TrayItem::new("MonitorHosts", IconSource::Resource("default-icon")).unwrap();
-            tray.inner_mut().set_tooltip("MonitorHosts").unwrap();
-
-            tray.add_label("MonitorHosts").unwrap();
-
-            tray.inner_mut().add_separator().unwrap();
-
-            let (tx, rx) = mpsc::sync_channel(1);
-
-            let tx_clone = tx.clone();
-            tray.add_menu_item("主界面", move || {
-                tx_clone.send(Message::ShowMainWindow).unwrap();
-            })
-            .unwrap();
-
-            // let tx_clone = tx.clone();
-            // tray.add_menu_item("配置", move || {
-            //     tx_clone.send(Message::Config).unwrap();
-            // })
-            // .unwrap();
-
-            // let tx_clone = tx.clone();
-            // tray.add_menu_item("关于", move || {
-            //     tx_clone.send(Message::About).unwrap();
-            // })
-            // .unwrap();
-
-            tray.inner_mut().add_separator().unwrap();
-
-            let tx_clone = tx.clone();
-            tray.add_menu_item("退出", move || {
-                tx_clone.send(Message::Quit).unwrap();
-            })
-            .unwrap();
+            let (tx, rx) = mpsc::channel();
+            let view = |manager: &Manager| View {
+                title: title.clone(),
+                health: manager.health(),
+                problems: manager.problems(),
+            };
+            let mut shown = view(&manager.read().unwrap());
+            let mut tray = match Backend::new(&shown, tx) {
+                Ok(tray) => tray,
+                Err(err) => {
+                    warn!("no tray available: {err}");
+                    without_tray(&window_weak);
+                    return;
+                }
+            };
 
             loop {
-                match rx.recv() {
+                let current = view(&manager.read().unwrap());
+                if current != shown {
+                    trace!("tray health: {:?}", current.health);
+                    tray.show(&current);
+                    shown = current;
+                }
+                match rx.recv_timeout(Duration::from_secs(1)) {
                     Ok(Message::Quit) => {
                         warn!("terminating...");
                         slint::quit_event_loop().unwrap();
                         break;
                     }
+                    Ok(Message::Unavailable) => {
+                        warn!("tray host went away");
+                        without_tray(&window_weak);
+                        break;
+                    }
                     Ok(Message::ShowMainWindow) => {
                         trace!("show main window...");
                         window_weak
@@ -69,13 +104,54 @@ impl Tray {
                             })
                             .unwrap();
                     }
-                    // Ok(Message::Config) => {
-                    //     println!("Config");
-                    // }
-                    // Ok(Message::About) => {
-                    //     println!("About");
-                    // }
-                    Err(err) => eprintln!("{}", err),
+                    Ok(Message::FocusHost(name)) => {
+                        trace!("focus host {name}...");
+                        let index = manager
+                            .read()
+                            .unwrap()
+                            .hosts
+                            .iter()
+                            .position(|host| host.name == name);
+                        window_weak
+                            .upgrade_in_event_loop(move |window| {
+                                window.show().unwrap();
+                                if let Some(index) = index {
+                                    window.invoke_focus_row(index as i32);
+                                }
+                            })
+                            .unwrap();
+                    }
+                    Ok(Message::TogglePause) => {
+                        trace!("toggle monitoring paused...");
+                        if !manager.write().unwrap().toggle_paused() {
+                            scheduler.probe_now(ProbeRequest::All);
+                        }
+                    }
+                    Ok(Message::Config) => {
+                        trace!("show settings...");
+                        let mgr = manager.clone();
+                        let config = config.clone();
+                        slint::invoke_from_event_loop(move || {
+                            dialog::settings_dialog(mgr, config).show().unwrap();
+                        })
+                        .unwrap();
+                    }
+                    Ok(Message::About) => {
+                        let message = format!(
+                            "MonitorHosts {}\n配置文件：{}",
+                            env!("CARGO_PKG_VERSION"),
+                            config.display()
+                        );
+                        slint::invoke_from_event_loop(move || {
+                            dialog::message_dialog("关于", &message).show().unwrap();
+                        })
+                        .unwrap();
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        error!("tray menu channel closed, stopping tray");
+                        break;
+                    }
                 }
             }
         });
@@ -89,9 +165,62 @@ impl Tray {
     }
 }
 
+/// What the tray shows.
+#[derive(Clone, Debug, PartialEq)]
+struct View {
+    title: String,
+    health: Health,
+    problems: Vec<Problem>,
+}
+
+impl View {
+    fn icon(&self) -> Icon {
+        let health = &self.health;
+        if health.up + health.degraded + health.down == 0 {
+            return Icon::Plain;
+        }
+        match health.status() {
+            PortStatus::On => Icon::Up,
+            PortStatus::Degraded => Icon::Degraded,
+            PortStatus::Off | PortStatus::Error => Icon::Down,
+        }
+    }
+}
+
+/// The bundled icons, colored by the worst state and plain without hosts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Icon {
+    Plain,
+    Up,
+    Degraded,
+    Down,
+}
+
+/// Without a tray the main window is the only way in, so it is shown and
+/// closing it quits.
+fn without_tray(window: &Weak<MainWindow>) {
+    let shown = window.upgrade_in_event_loop(|window| {
+        window.window().on_close_requested(|| {
+            slint::quit_event_loop().unwrap();
+            CloseRequestResponse::HideWindow
+        });
+        window.show().unwrap();
+    });
+    if let Err(err) = shown {
+        warn!("failed to show main window without tray: {err}");
+    }
+}
+
+// Only the StatusNotifierItem backend sends every message.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+#[derive(Clone, Debug)]
 pub enum Message {
-    // About,
-    // Config,
+    About,
+    Config,
     ShowMainWindow,
+    FocusHost(String),
+    TogglePause,
+    /// The tray cannot be shown any more.
+    Unavailable,
     Quit,
 }
@@ -0,0 +1,182 @@
+use std::cell::RefCell;
+use std::sync::mpsc::{self, Sender};
+
+use log::{error, trace};
+use tray_icon::menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tray_icon::{Icon as Image, TrayIcon, TrayIconBuilder};
+
+use super::{Message, View, ACTIONS, MAX_PROBLEMS};
+use crate::manager::Problem;
+
+thread_local! {
+    /// AppKit only allows the status item on the main thread, which runs the
+    /// Slint event loop, so it is kept there and reached through the loop.
+    static TRAY: RefCell<Option<StatusItem>> = const { RefCell::new(None) };
+}
+
+/// A status item in the menu bar with the bundled PNG icons.
+pub struct Backend;
+
+impl Backend {
+    pub fn new(view: &View, tx: Sender<Message>) -> Result<Self, String> {
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            match message(event.id.as_ref()) {
+                Some(message) => tx.send(message).unwrap(),
+                None => trace!("unknown tray menu item {:?}", event.id),
+            }
+        }));
+        let (done, created) = mpsc::channel();
+        let view = view.clone();
+        slint::invoke_from_event_loop(move || {
+            let result = StatusItem::new(&view).map(|item| {
+                TRAY.with(|tray| *tray.borrow_mut() = Some(item));
+            });
+            done.send(result).unwrap();
+        })
+        .map_err(|err| err.to_string())?;
+        created.recv().map_err(|err| err.to_string())??;
+        Ok(Self)
+    }
+
+    pub fn show(&mut self, view: &View) {
+        let view = view.clone();
+        let shown = slint::invoke_from_event_loop(move || {
+            TRAY.with(|tray| {
+                if let Some(item) = tray.borrow_mut().as_mut() {
+                    item.show(&view);
+                }
+            });
+        });
+        if let Err(err) = shown {
+            error!("failed to update tray: {err}");
+        }
+    }
+}
+
+struct StatusItem {
+    tray: TrayIcon,
+    problems: Vec<Problem>,
+    /// Indexed by [`super::Icon`].
+    icons: [Image; 4],
+}
+
+impl StatusItem {
+    fn new(view: &View) -> Result<Self, String> {
+        let icons = [
+            image(include_bytes!("../../resources/icons/monitorhosts.png")),
+            image(include_bytes!("../../resources/icons/monitorhosts-up.png")),
+            image(include_bytes!(
+                "../../resources/icons/monitorhosts-degraded.png"
+            )),
+            image(include_bytes!(
+                "../../resources/icons/monitorhosts-down.png"
+            )),
+        ];
+        let tray = TrayIconBuilder::new()
+            .with_menu(Box::new(menu(view)?))
+            .with_icon(icons[view.icon() as usize].clone())
+            .with_tooltip(tooltip(view))
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(Self {
+            tray,
+            problems: view.problems.clone(),
+            icons,
+        })
+    }
+
+    fn show(&mut self, view: &View) {
+        if view.problems != self.problems {
+            trace!("tray problems changed, rebuilding menu...");
+            match menu(view) {
+                Ok(menu) => {
+                    self.tray.set_menu(Some(Box::new(menu)));
+                    self.problems = view.problems.clone();
+                }
+                Err(err) => error!("failed to rebuild tray menu: {err}"),
+            }
+        }
+        let icon = self.icons[view.icon() as usize].clone();
+        if let Err(err) = self.tray.set_icon(Some(icon)) {
+            error!("failed to set tray icon: {err}");
+        }
+        if let Err(err) = self.tray.set_tooltip(Some(tooltip(view))) {
+            error!("failed to set tray tooltip: {err}");
+        }
+    }
+}
+
+/// The menu listing the problems of `view`. Item ids are turned back into
+/// messages by [`message`].
+fn menu(view: &View) -> Result<Menu, String> {
+    let menu = Menu::new();
+    let append = |item: &dyn IsMenuItem| menu.append(item).map_err(|err| err.to_string());
+    append(&MenuItem::new(&view.title, false, None))?;
+    append(&PredefinedMenuItem::separator())?;
+
+    let problems = &view.problems;
+    if !problems.is_empty() {
+        let submenu = Submenu::new(format!("故障主机（{}）", problems.len()), true);
+        for problem in problems.iter().take(MAX_PROBLEMS) {
+            let id = format!("host:{}", problem.name);
+            let item = MenuItem::with_id(id, problem.to_string(), true, None);
+            submenu.append(&item).map_err(|err| err.to_string())?;
+        }
+        if problems.len() > MAX_PROBLEMS {
+            let more = format!("…另有 {} 台", problems.len() - MAX_PROBLEMS);
+            submenu
+                .append(&MenuItem::new(more, false, None))
+                .map_err(|err| err.to_string())?;
+        }
+        append(&submenu)?;
+        append(&PredefinedMenuItem::separator())?;
+    }
+
+    for (index, (label, _)) in ACTIONS.iter().enumerate() {
+        append(&MenuItem::with_id(
+            format!("action:{index}"),
+            label,
+            true,
+            None,
+        ))?;
+    }
+    append(&PredefinedMenuItem::separator())?;
+    append(&MenuItem::with_id("quit", "退出", true, None))?;
+    Ok(menu)
+}
+
+fn message(id: &str) -> Option<Message> {
+    match id.split_once(':') {
+        Some(("host", name)) => Some(Message::FocusHost(name.to_owned())),
+        Some(("action", index)) => {
+            let index: usize = index.parse().ok()?;
+            ACTIONS.into_iter().nth(index).map(|(_, message)| message)
+        }
+        _ if id == "quit" => Some(Message::Quit),
+        _ => None,
+    }
+}
+
+fn tooltip(view: &View) -> String {
+    format!("{}\n{}", view.title, view.health)
+}
+
+/// Decodes a bundled PNG into the RGBA image the status item expects.
+fn image(png: &[u8]) -> Image {
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().expect("bundled icon is a valid PNG");
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .expect("bundled icon is a valid PNG");
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buf[..info.buffer_size()].to_vec(),
+        png::ColorType::Rgb => buf[..info.buffer_size()]
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 0xff])
+            .collect(),
+        other => panic!("unexpected icon color type {other:?}"),
+    };
+    Image::from_rgba(rgba, info.width, info.height).expect("bundled icon has a valid size")
+}
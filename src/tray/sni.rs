@@ -0,0 +1,178 @@
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use ksni::menu::{StandardItem, SubMenu};
+use ksni::{Handle, MenuItem, ToolTip, TrayService};
+use log::{error, trace};
+
+use super::{Message, View, ACTIONS, MAX_PROBLEMS};
+
+/// A StatusNotifierItem, shown by KDE, or by GNOME with the AppIndicator
+/// extension, with the bundled PNG icons.
+pub struct Backend {
+    handle: Handle<Model>,
+}
+
+impl Backend {
+    pub fn new(view: &View, tx: Sender<Message>) -> Result<Self, String> {
+        let model = Model {
+            view: view.clone(),
+            icons: [
+                pixmap(include_bytes!("../../resources/icons/monitorhosts.png")),
+                pixmap(include_bytes!("../../resources/icons/monitorhosts-up.png")),
+                pixmap(include_bytes!(
+                    "../../resources/icons/monitorhosts-degraded.png"
+                )),
+                pixmap(include_bytes!(
+                    "../../resources/icons/monitorhosts-down.png"
+                )),
+            ],
+            tx: tx.clone(),
+        };
+        let service = TrayService::new(model);
+        let handle = service.handle();
+        thread::Builder::new()
+            .name("tray".to_owned())
+            .spawn(move || {
+                // Fails without a session bus; a missing tray host is reported
+                // through `watcher_offine` instead.
+                if let Err(err) = service.run() {
+                    error!("tray service failed: {err}");
+                    let _ = tx.send(Message::Unavailable);
+                }
+            })
+            .map_err(|err| err.to_string())?;
+        Ok(Self { handle })
+    }
+
+    pub fn show(&mut self, view: &View) {
+        self.handle.update(|model| model.view = view.clone());
+    }
+}
+
+struct Model {
+    view: View,
+    /// Indexed by [`super::Icon`].
+    icons: [ksni::Icon; 4],
+    tx: Sender<Message>,
+}
+
+impl Model {
+    fn item(label: &str, message: Message) -> MenuItem<Self> {
+        StandardItem {
+            label: mnemonic(label),
+            activate: Box::new(move |model: &mut Self| {
+                model.tx.send(message.clone()).unwrap();
+            }),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl ksni::Tray for Model {
+    fn id(&self) -> String {
+        "monitorhosts".to_owned()
+    }
+
+    fn title(&self) -> String {
+        self.view.title.clone()
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        vec![self.icons[self.view.icon() as usize].clone()]
+    }
+
+    fn tool_tip(&self) -> ToolTip {
+        ToolTip {
+            title: self.view.title.clone(),
+            description: self.view.health.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.tx.send(Message::ShowMainWindow).unwrap();
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let mut menu = Vec::new();
+        let problems = &self.view.problems;
+        if !problems.is_empty() {
+            let mut submenu: Vec<MenuItem<Self>> = problems
+                .iter()
+                .take(MAX_PROBLEMS)
+                .map(|problem| {
+                    Self::item(
+                        &problem.to_string(),
+                        Message::FocusHost(problem.name.clone()),
+                    )
+                })
+                .collect();
+            if problems.len() > MAX_PROBLEMS {
+                submenu.push(
+                    StandardItem {
+                        label: format!("…另有 {} 台", problems.len() - MAX_PROBLEMS),
+                        enabled: false,
+                        ..Default::default()
+                    }
+                    .into(),
+                );
+            }
+            menu.push(
+                SubMenu {
+                    label: format!("故障主机（{}）", problems.len()),
+                    submenu,
+                    ..Default::default()
+                }
+                .into(),
+            );
+            menu.push(MenuItem::Separator);
+        }
+        for (label, message) in ACTIONS {
+            menu.push(Self::item(label, message));
+        }
+        menu.push(MenuItem::Separator);
+        menu.push(Self::item("退出", Message::Quit));
+        menu
+    }
+
+    fn watcher_offine(&self) -> bool {
+        trace!("no StatusNotifierWatcher");
+        let _ = self.tx.send(Message::Unavailable);
+        false
+    }
+}
+
+/// Underscores mark access keys in menu labels, so host names keep theirs
+/// by doubling them.
+fn mnemonic(label: &str) -> String {
+    label.replace('_', "__")
+}
+
+/// Decodes a bundled PNG into the ARGB32 pixmap the tray host expects.
+fn pixmap(png: &[u8]) -> ksni::Icon {
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().expect("bundled icon is a valid PNG");
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .expect("bundled icon is a valid PNG");
+    let data = match info.color_type {
+        png::ColorType::Rgba => buf[..info.buffer_size()]
+            .chunks_exact(4)
+            .flat_map(|p| [p[3], p[0], p[1], p[2]])
+            .collect(),
+        png::ColorType::Rgb => buf[..info.buffer_size()]
+            .chunks_exact(3)
+            .flat_map(|p| [0xff, p[0], p[1], p[2]])
+            .collect(),
+        other => panic!("unexpected icon color type {other:?}"),
+    };
+    ksni::Icon {
+        width: info.width as i32,
+        height: info.height as i32,
+        data,
+    }
+}
@@ -0,0 +1,14 @@
+use std::sync::mpsc::Sender;
+
+use super::{Message, View};
+
+/// Platforms without a tray backend; the main window is shown instead.
+pub struct Backend;
+
+impl Backend {
+    pub fn new(_view: &View, _tx: Sender<Message>) -> Result<Self, String> {
+        Err("当前系统暂不支持托盘图标".to_owned())
+    }
+
+    pub fn show(&mut self, _view: &View) {}
+}
@@ -0,0 +1,101 @@
+use std::sync::mpsc::Sender;
+
+use log::{error, trace};
+use tray_item::{IconSource, TIError, TrayItem};
+
+use super::{Icon, Message, View, ACTIONS, MAX_PROBLEMS};
+use crate::manager::Problem;
+
+/// The notification area icon, with the icons embedded as resources.
+pub struct Backend {
+    tray: TrayItem,
+    problems: Vec<Problem>,
+    tx: Sender<Message>,
+}
+
+impl Backend {
+    pub fn new(view: &View, tx: Sender<Message>) -> Result<Self, String> {
+        let tray = build(view, &tx).map_err(|err| err.to_string())?;
+        let mut backend = Self {
+            tray,
+            problems: view.problems.clone(),
+            tx,
+        };
+        backend.show(view);
+        Ok(backend)
+    }
+
+    pub fn show(&mut self, view: &View) {
+        if view.problems != self.problems {
+            // Menu items cannot be removed, so the whole tray is rebuilt.
+            trace!("tray problems changed, rebuilding menu...");
+            match build(view, &self.tx) {
+                Ok(tray) => {
+                    self.tray = tray;
+                    self.problems = view.problems.clone();
+                }
+                Err(err) => error!("failed to rebuild tray menu: {err}"),
+            }
+        }
+        if let Err(err) = self
+            .tray
+            .set_icon(IconSource::Resource(resource(view.icon())))
+        {
+            error!("failed to set tray icon: {err}");
+        }
+        let tooltip = format!("{}\n{}", view.title, view.health);
+        if let Err(err) = self.tray.inner_mut().set_tooltip(&tooltip) {
+            error!("failed to set tray tooltip: {err}");
+        }
+    }
+}
+
+/// Creates the tray with the menu listing the problems of `view`.
+fn build(view: &View, tx: &Sender<Message>) -> Result<TrayItem, TIError> {
+    let mut tray = TrayItem::new("MonitorHosts", IconSource::Resource("default-icon"))?;
+
+    tray.add_label(&view.title)?;
+
+    tray.inner_mut().add_separator()?;
+
+    let problems = &view.problems;
+    if !problems.is_empty() {
+        tray.add_label(&format!("故障主机（{}）", problems.len()))?;
+        for problem in problems.iter().take(MAX_PROBLEMS) {
+            let tx_clone = tx.clone();
+            let name = problem.name.clone();
+            tray.add_menu_item(&problem.to_string(), move || {
+                tx_clone.send(Message::FocusHost(name.clone())).unwrap();
+            })?;
+        }
+        if problems.len() > MAX_PROBLEMS {
+            tray.add_label(&format!("…另有 {} 台", problems.len() - MAX_PROBLEMS))?;
+        }
+        tray.inner_mut().add_separator()?;
+    }
+
+    for (label, message) in ACTIONS {
+        let tx_clone = tx.clone();
+        tray.add_menu_item(label, move || {
+            tx_clone.send(message.clone()).unwrap();
+        })?;
+    }
+
+    tray.inner_mut().add_separator()?;
+
+    let tx_clone = tx.clone();
+    tray.add_menu_item("退出", move || {
+        tx_clone.send(Message::Quit).unwrap();
+    })?;
+
+    Ok(tray)
+}
+
+fn resource(icon: Icon) -> &'static str {
+    match icon {
+        Icon::Plain => "default-icon",
+        Icon::Up => "up-icon",
+        Icon::Degraded => "degraded-icon",
+        Icon::Down => "down-icon",
+    }
+}